@@ -1,8 +1,78 @@
-use std::{env::set_current_dir, io, path::Path, process::Command};
+use std::{
+    env::set_current_dir,
+    fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+};
 
 const FLATC_BINARY: &str = if cfg!(windows) { "flatc.exe" } else { "flatc" };
 const OUT_FOLDER: &str = "./src/generated";
 const SCHEMA_FOLDER: &str = "./flatbuffers-schema";
+const SERDE_ENUMS_FILE: &str = "serde_enums.rs";
+
+const SERDE_DERIVE: &str =
+    "#[cfg_attr(feature = \"serde\", derive(serde::Serialize, serde::Deserialize))]";
+const SERDE_DERIVE_DEFAULT: &str = "#[cfg_attr(feature = \"serde\", derive(serde::Serialize, serde::Deserialize), serde(default))]";
+const ENUM_REPR_TYPES: [&str; 8] = ["u8", "i8", "u16", "i16", "u32", "i32", "u64", "i64"];
+
+fn rust_files(folder: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(folder)? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            rust_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Tags every generated object API type with a `serde` derive behind the `serde` feature,
+/// and collects the names of the generated enums which get their serde impls in `serde_impls.rs`
+fn add_serde_attributes(file: &Path, enums: &mut Vec<String>) -> io::Result<()> {
+    let contents = fs::read_to_string(file)?;
+    let mut output = String::with_capacity(contents.len() + 1024);
+
+    for line in contents.lines() {
+        let trimmed = line.trim_start();
+        let indent = &line[..line.len() - trimmed.len()];
+
+        if let Some(name) = trimmed
+            .strip_prefix("pub struct ")
+            .and_then(|rest| rest.strip_suffix(" {"))
+        {
+            if name.ends_with('T') {
+                output.push_str(indent);
+                output.push_str(SERDE_DERIVE_DEFAULT);
+                output.push('\n');
+            }
+        } else if let Some(name) = trimmed
+            .strip_prefix("pub enum ")
+            .and_then(|rest| rest.strip_suffix(" {"))
+        {
+            if name.ends_with('T') {
+                output.push_str(indent);
+                output.push_str(SERDE_DERIVE);
+                output.push('\n');
+            }
+        } else if let Some((name, repr)) = trimmed
+            .strip_prefix("pub struct ")
+            .and_then(|rest| rest.strip_suffix(");"))
+            .and_then(|rest| rest.split_once("(pub "))
+        {
+            if ENUM_REPR_TYPES.contains(&repr) {
+                enums.push(name.to_string());
+            }
+        }
+
+        output.push_str(line);
+        output.push('\n');
+    }
+
+    fs::write(file, output)
+}
 
 fn main() -> io::Result<()> {
     println!("cargo:rerun-if-changed=flatbuffers-schema/comms.fbs");
@@ -45,5 +115,19 @@ fn main() -> io::Result<()> {
         out_folder.display()
     );
 
+    let mut files = Vec::new();
+    rust_files(&out_folder, &mut files)?;
+
+    let mut enums = Vec::new();
+    for file in &files {
+        add_serde_attributes(file, &mut enums)?;
+    }
+
+    enums.sort();
+    fs::write(
+        Path::new(OUT_FOLDER).join(SERDE_ENUMS_FILE),
+        format!("impl_enum_serde!({});\n", enums.join(", ")),
+    )?;
+
     Ok(())
 }
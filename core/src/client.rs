@@ -0,0 +1,302 @@
+use crate::{flat, FrameReader, FrameWriter, Message, ProtocolError, SocketDataType};
+use flatbuffers::FlatBufferBuilder;
+use std::{env, error::Error, fmt::Display, io};
+use tokio::net::{
+    tcp::{OwnedReadHalf, OwnedWriteHalf},
+    TcpStream, ToSocketAddrs,
+};
+
+pub const DEFAULT_SERVER_PORT: u16 = 23234;
+
+#[derive(Debug)]
+pub enum ClientError {
+    Protocol(ProtocolError),
+    MissingAgentId,
+    InvalidServerPort(String),
+    ClosedBeforeInit,
+}
+
+impl Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Protocol(e) => e.fmt(f),
+            Self::MissingAgentId => write!(f, "RLBOT_AGENT_ID is not set"),
+            Self::InvalidServerPort(port) => write!(f, "Invalid RLBOT_SERVER_PORT - {port}"),
+            Self::ClosedBeforeInit => {
+                write!(f, "Server closed the connection before sending match info")
+            }
+        }
+    }
+}
+
+impl Error for ClientError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Protocol(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ProtocolError> for ClientError {
+    #[inline]
+    fn from(e: ProtocolError) -> Self {
+        Self::Protocol(e)
+    }
+}
+
+impl From<io::Error> for ClientError {
+    #[inline]
+    fn from(e: io::Error) -> Self {
+        Self::Protocol(e.into())
+    }
+}
+
+/// A framed TCP connection to an RLBot server
+pub struct Connection {
+    reader: FrameReader<OwnedReadHalf>,
+    writer: FrameWriter<OwnedWriteHalf>,
+    builder: FlatBufferBuilder<'static>,
+}
+
+impl Connection {
+    pub async fn connect(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let tcp = TcpStream::connect(addr).await?;
+        tcp.set_nodelay(true)?;
+        let (reader, writer) = tcp.into_split();
+
+        Ok(Self {
+            reader: FrameReader::new(reader),
+            writer: FrameWriter::new(writer),
+            builder: FlatBufferBuilder::with_capacity(1024),
+        })
+    }
+
+    /// Connects to the local server using `RLBOT_SERVER_PORT`, falling back to the default port
+    pub async fn connect_from_env() -> Result<Self, ClientError> {
+        let port = match env::var("RLBOT_SERVER_PORT") {
+            Ok(port) => port
+                .parse()
+                .map_err(|_| ClientError::InvalidServerPort(port))?,
+            Err(_) => DEFAULT_SERVER_PORT,
+        };
+
+        Ok(Self::connect(("127.0.0.1", port)).await?)
+    }
+
+    /// Queues a message to be sent on the next [`Connection::flush`]
+    #[inline]
+    pub fn queue(&mut self, message: &Message) -> Result<(), ProtocolError> {
+        self.writer.queue_message(message, &mut self.builder)
+    }
+
+    #[inline]
+    pub async fn flush(&mut self) -> Result<(), ProtocolError> {
+        self.writer.flush().await
+    }
+
+    pub async fn send(&mut self, message: &Message) -> Result<(), ProtocolError> {
+        self.queue(message)?;
+        self.flush().await
+    }
+
+    #[inline]
+    pub async fn recv(&mut self) -> Result<Message, ProtocolError> {
+        self.reader.read_message().await
+    }
+
+    /// Skips over incoming frames until one of `data_type` arrives, without decoding any of them
+    pub async fn wait_for(&mut self, data_type: SocketDataType) -> Result<(), ProtocolError> {
+        loop {
+            let (received_type, _) = self.reader.read_frame().await?;
+            if received_type == data_type {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Messages that an [`Agent`] wants sent alongside its next input
+pub struct Outgoing {
+    index: u32,
+    team: u32,
+    messages: Vec<Message>,
+}
+
+impl Outgoing {
+    #[inline]
+    pub const fn index(&self) -> u32 {
+        self.index
+    }
+
+    #[inline]
+    pub const fn team(&self) -> u32 {
+        self.team
+    }
+
+    /// Adds or replaces the render group with the given id
+    pub fn render(&mut self, id: i32, render_messages: Vec<flat::RenderMessageT>) {
+        let mut group = flat::RenderGroupT::default();
+        group.id = id;
+        group.render_messages = render_messages;
+
+        self.messages.push(Message::RenderGroup(group));
+    }
+
+    pub fn remove_render(&mut self, id: i32) {
+        let mut group = flat::RemoveRenderGroupT::default();
+        group.id = id;
+
+        self.messages.push(Message::RemoveRenderGroup(group));
+    }
+
+    pub fn send_match_comm(&mut self, content: Vec<u8>, display: Option<String>, team_only: bool) {
+        let mut comm = flat::MatchCommT::default();
+        comm.index = self.index;
+        comm.team = self.team;
+        comm.team_only = team_only;
+        comm.display = display;
+        comm.content = content;
+
+        self.messages.push(Message::MatchComm(comm));
+    }
+
+    pub fn set_game_state(&mut self, desired_state: flat::DesiredGameStateT) {
+        self.messages.push(Message::DesiredGameState(desired_state));
+    }
+}
+
+/// A bot that controls a single car
+pub trait Agent: Sized {
+    fn new(
+        controllable: &flat::ControllableInfoT,
+        team: u32,
+        match_config: &flat::MatchConfigurationT,
+        field_info: &flat::FieldInfoT,
+    ) -> Self;
+
+    /// Called for every game packet along with the latest prediction of the first ball,
+    /// the returned controls are sent back for this agent's car
+    fn on_packet(
+        &mut self,
+        packet: &flat::GamePacketT,
+        ball_prediction: Option<&flat::BallPredictionT>,
+        outgoing: &mut Outgoing,
+    ) -> flat::ControllerStateT;
+
+    fn on_match_comm(&mut self, _comm: &flat::MatchCommT, _outgoing: &mut Outgoing) {}
+}
+
+struct AgentSlot<A> {
+    agent: A,
+    outgoing: Outgoing,
+}
+
+impl<A> AgentSlot<A> {
+    fn flush_into(&mut self, connection: &mut Connection) -> Result<(), ProtocolError> {
+        for message in self.outgoing.messages.drain(..) {
+            connection.queue(&message)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Connects using `RLBOT_SERVER_PORT` and `RLBOT_AGENT_ID`,
+/// then runs one `A` for every car the server hands out until the connection is closed
+pub async fn run_agents<A: Agent>() -> Result<(), ClientError> {
+    let agent_id = env::var("RLBOT_AGENT_ID").map_err(|_| ClientError::MissingAgentId)?;
+    let mut connection = Connection::connect_from_env().await?;
+
+    let mut connection_settings = flat::ConnectionSettingsT::default();
+    connection_settings.agent_id = agent_id;
+    connection_settings.wants_ball_predictions = true;
+    connection_settings.wants_comms = true;
+    connection_settings.close_between_matches = true;
+    connection
+        .send(&Message::ConnectionSettings(connection_settings))
+        .await?;
+
+    let mut match_config = None;
+    let mut field_info = None;
+    let mut team_info = None;
+
+    while match_config.is_none() || field_info.is_none() || team_info.is_none() {
+        match connection.recv().await? {
+            Message::None => return Err(ClientError::ClosedBeforeInit),
+            Message::MatchConfig(config) => match_config = Some(config),
+            Message::FieldInfo(info) => field_info = Some(info),
+            Message::ControllableTeamInfo(info) => team_info = Some(info),
+            _ => {}
+        }
+    }
+
+    let (Some(match_config), Some(field_info), Some(team_info)) =
+        (match_config, field_info, team_info)
+    else {
+        unreachable!()
+    };
+
+    let mut agents: Vec<_> = team_info
+        .controllables
+        .iter()
+        .map(|controllable| AgentSlot {
+            agent: A::new(controllable, team_info.team, &match_config, &field_info),
+            outgoing: Outgoing {
+                index: controllable.index,
+                team: team_info.team,
+                messages: Vec::new(),
+            },
+        })
+        .collect();
+
+    connection.send(&Message::InitComplete).await?;
+
+    let mut ball_prediction = None;
+    // the server sends one prediction per ball before each packet, agents only get the first ball's
+    let mut predictions_since_packet = 0;
+
+    loop {
+        match connection.recv().await? {
+            Message::None => break,
+            Message::BallPrediction(prediction) => {
+                if predictions_since_packet == 0 {
+                    ball_prediction = Some(prediction);
+                }
+                predictions_since_packet += 1;
+            }
+            Message::GamePacket(packet) => {
+                predictions_since_packet = 0;
+
+                for slot in &mut agents {
+                    let mut input = flat::PlayerInputT::default();
+                    input.player_index = slot.outgoing.index;
+                    input.controller_state =
+                        slot.agent
+                            .on_packet(&packet, ball_prediction.as_ref(), &mut slot.outgoing);
+
+                    connection.queue(&Message::PlayerInput(input))?;
+                    slot.flush_into(&mut connection)?;
+                }
+
+                connection.flush().await?;
+            }
+            Message::MatchComm(comm) => {
+                for slot in &mut agents {
+                    // don't echo our own messages back to ourselves
+                    if comm.index == slot.outgoing.index {
+                        continue;
+                    }
+
+                    slot.agent.on_match_comm(&comm, &mut slot.outgoing);
+                    slot.flush_into(&mut connection)?;
+                }
+
+                connection.flush().await?;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
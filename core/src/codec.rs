@@ -0,0 +1,269 @@
+use crate::{Message, ProtocolError, SocketDataType};
+use flatbuffers::FlatBufferBuilder;
+use std::io;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Every frame starts with a big-endian u16 data type followed by a big-endian u16 payload length
+pub const HEADER_LEN: usize = 4;
+
+/// Reads `u16 type + u16 length` frames from an [`AsyncRead`].
+///
+/// Reads are buffered internally, so [`FrameReader::read_frame`] is cancel safe
+/// and can be used as a branch in `tokio::select!`.
+pub struct FrameReader<R> {
+    reader: R,
+    buffer: Vec<u8>,
+    consumed: usize,
+}
+
+impl<R: AsyncRead + Unpin> FrameReader<R> {
+    #[inline]
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buffer: Vec::with_capacity(1024),
+            consumed: 0,
+        }
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn buffered_frame_len(&self) -> Option<usize> {
+        if self.buffer.len() < HEADER_LEN {
+            return None;
+        }
+
+        let payload_len = usize::from(u16::from_be_bytes([self.buffer[2], self.buffer[3]]));
+        let frame_len = HEADER_LEN + payload_len;
+        (self.buffer.len() >= frame_len).then_some(frame_len)
+    }
+
+    /// Waits for the next complete frame and returns its data type and raw payload
+    pub async fn read_frame(&mut self) -> Result<(SocketDataType, &[u8]), ProtocolError> {
+        self.buffer.drain(..self.consumed);
+        self.consumed = 0;
+
+        let frame_len = loop {
+            if let Some(frame_len) = self.buffered_frame_len() {
+                break frame_len;
+            }
+
+            self.buffer.reserve(1024);
+            if self.reader.read_buf(&mut self.buffer).await? == 0 {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+        };
+
+        // skip over the frame even if the data type turns out to be invalid
+        self.consumed = frame_len;
+
+        let data_type =
+            SocketDataType::try_from(u16::from_be_bytes([self.buffer[0], self.buffer[1]]))?;
+
+        Ok((data_type, &self.buffer[HEADER_LEN..frame_len]))
+    }
+
+    /// Waits for the next complete frame and decodes it into a [`Message`]
+    pub async fn read_message(&mut self) -> Result<Message, ProtocolError> {
+        let (data_type, payload) = self.read_frame().await?;
+        Message::decode(data_type, payload)
+    }
+}
+
+/// Writes `u16 type + u16 length` frames to an [`AsyncWrite`].
+///
+/// Frames are queued into a single buffer and only sent on [`FrameWriter::flush`],
+/// so several frames can be batched into one write.
+pub struct FrameWriter<W> {
+    writer: W,
+    buffer: Vec<u8>,
+}
+
+impl<W: AsyncWrite + Unpin> FrameWriter<W> {
+    #[inline]
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            buffer: Vec::with_capacity(1024),
+        }
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    pub fn queue_frame(
+        &mut self,
+        data_type: SocketDataType,
+        payload: &[u8],
+    ) -> Result<(), ProtocolError> {
+        let size = u16::try_from(payload.len())
+            .map_err(|_| ProtocolError::PayloadTooLarge(payload.len()))?;
+
+        self.buffer.reserve(HEADER_LEN + payload.len());
+        self.buffer
+            .extend_from_slice(&(data_type as u16).to_be_bytes());
+        self.buffer.extend_from_slice(&size.to_be_bytes());
+        self.buffer.extend_from_slice(payload);
+
+        Ok(())
+    }
+
+    pub fn queue_message(
+        &mut self,
+        message: &Message,
+        builder: &mut FlatBufferBuilder,
+    ) -> Result<(), ProtocolError> {
+        let payload = message.encode(builder);
+        self.queue_frame(message.data_type(), payload)
+    }
+
+    /// Writes out every queued frame
+    pub async fn flush(&mut self) -> Result<(), ProtocolError> {
+        if !self.buffer.is_empty() {
+            self.writer.write_all(&self.buffer).await?;
+            self.buffer.clear();
+        }
+
+        self.writer.flush().await?;
+        Ok(())
+    }
+
+    pub async fn send_frame(
+        &mut self,
+        data_type: SocketDataType,
+        payload: &[u8],
+    ) -> Result<(), ProtocolError> {
+        self.queue_frame(data_type, payload)?;
+        self.flush().await
+    }
+
+    pub async fn send_message(
+        &mut self,
+        message: &Message,
+        builder: &mut FlatBufferBuilder<'_>,
+    ) -> Result<(), ProtocolError> {
+        self.queue_message(message, builder)?;
+        self.flush().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InvalidSocketDataType;
+    use tokio::io::{duplex, DuplexStream};
+
+    fn frame_pair() -> (FrameReader<DuplexStream>, FrameWriter<DuplexStream>) {
+        let (client, server) = duplex(1 << 17);
+        (FrameReader::new(client), FrameWriter::new(server))
+    }
+
+    fn raw_frame(data_type: u16, payload: &[u8]) -> Vec<u8> {
+        let mut frame = data_type.to_be_bytes().to_vec();
+        frame.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    #[tokio::test]
+    async fn queued_frame_is_read_back() {
+        let (mut reader, mut writer) = frame_pair();
+
+        writer
+            .queue_frame(SocketDataType::MatchComm, &[1, 2, 3])
+            .unwrap();
+        writer.flush().await.unwrap();
+
+        let (data_type, payload) = reader.read_frame().await.unwrap();
+        assert_eq!(data_type, SocketDataType::MatchComm);
+        assert_eq!(payload, [1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn frame_split_across_reads() {
+        let (mut reader, writer) = frame_pair();
+        let mut writer = writer.into_inner();
+
+        let frame = raw_frame(SocketDataType::GamePacket as u16, &[4; 10]);
+        let (start, end) = frame.split_at(3);
+        writer.write_all(start).await.unwrap();
+
+        let (frame, ()) = tokio::join!(reader.read_frame(), async {
+            // let the reader see the partial header first
+            tokio::task::yield_now().await;
+            writer.write_all(end).await.unwrap();
+        });
+
+        let (data_type, payload) = frame.unwrap();
+        assert_eq!(data_type, SocketDataType::GamePacket);
+        assert_eq!(payload, [4; 10]);
+    }
+
+    #[tokio::test]
+    async fn several_frames_in_one_write() {
+        let (mut reader, writer) = frame_pair();
+        let mut writer = writer.into_inner();
+
+        let mut bytes = raw_frame(SocketDataType::PlayerInput as u16, &[1]);
+        bytes.extend(raw_frame(SocketDataType::StopCommand as u16, &[]));
+        bytes.extend(raw_frame(SocketDataType::MatchComm as u16, &[2, 3]));
+        writer.write_all(&bytes).await.unwrap();
+
+        let (data_type, payload) = reader.read_frame().await.unwrap();
+        assert_eq!(
+            (data_type, payload),
+            (SocketDataType::PlayerInput, &[1][..])
+        );
+        let (data_type, payload) = reader.read_frame().await.unwrap();
+        assert_eq!((data_type, payload), (SocketDataType::StopCommand, &[][..]));
+        let (data_type, payload) = reader.read_frame().await.unwrap();
+        assert_eq!(
+            (data_type, payload),
+            (SocketDataType::MatchComm, &[2, 3][..])
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_data_type_keeps_the_stream_in_sync() {
+        let (mut reader, writer) = frame_pair();
+        let mut writer = writer.into_inner();
+
+        let mut bytes = raw_frame(999, &[7; 5]);
+        bytes.extend(raw_frame(SocketDataType::MatchComm as u16, &[8]));
+        writer.write_all(&bytes).await.unwrap();
+
+        assert!(matches!(
+            reader.read_frame().await,
+            Err(ProtocolError::InvalidDataType(InvalidSocketDataType(999)))
+        ));
+
+        // the bad frame's payload was skipped, not parsed as the next header
+        let (data_type, payload) = reader.read_frame().await.unwrap();
+        assert_eq!(data_type, SocketDataType::MatchComm);
+        assert_eq!(payload, [8]);
+    }
+
+    #[tokio::test]
+    async fn oversized_payload_is_rejected() {
+        let (mut reader, mut writer) = frame_pair();
+
+        let payload = vec![0; usize::from(u16::MAX) + 1];
+        assert!(matches!(
+            writer.queue_frame(SocketDataType::MatchComm, &payload),
+            Err(ProtocolError::PayloadTooLarge(len)) if len == payload.len()
+        ));
+
+        // nothing of the rejected frame was queued
+        writer.queue_frame(SocketDataType::MatchComm, &[9]).unwrap();
+        writer.flush().await.unwrap();
+
+        let (data_type, payload) = reader.read_frame().await.unwrap();
+        assert_eq!(data_type, SocketDataType::MatchComm);
+        assert_eq!(payload, [9]);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::flat::GameMode;
+use crate::flat::{GameMode, MatchLengthMutator};
 use std::{error::Error, fmt::Display, str::FromStr};
 
 #[derive(Clone, Debug)]
@@ -30,3 +30,22 @@ impl FromStr for GameMode {
         }
     }
 }
+
+impl FromStr for MatchLengthMutator {
+    type Err = EnumFromStrError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s
+            .to_lowercase()
+            .replace(|c: char| c == '_' || c == '-' || c.is_whitespace(), "");
+
+        match normalized.as_str() {
+            "fiveminutes" | "5minutes" => Ok(Self::FiveMinutes),
+            "tenminutes" | "10minutes" => Ok(Self::TenMinutes),
+            "twentyminutes" | "20minutes" => Ok(Self::TwentyMinutes),
+            "unlimited" => Ok(Self::Unlimited),
+            _ => Err(Self::Err {
+                name: s.to_string(),
+            }),
+        }
+    }
+}
@@ -1,9 +1,18 @@
+pub mod client;
+pub mod codec;
 mod from_str;
 #[allow(clippy::all, non_snake_case, unused_imports)]
 mod generated;
+mod message;
+#[cfg(feature = "serde")]
+mod serde_impls;
 
+use std::{error::Error, fmt::Display};
+
+pub use codec::{FrameReader, FrameWriter};
 pub use flatbuffers;
 pub use generated::rlbot::flat;
+pub use message::{Message, ProtocolError};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum SocketDataType {
@@ -25,11 +34,23 @@ pub enum SocketDataType {
     ControllableTeamInfo,
 }
 
-impl SocketDataType {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidSocketDataType(pub u16);
+
+impl Display for InvalidSocketDataType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid socket data type: {}", self.0)
+    }
+}
+
+impl Error for InvalidSocketDataType {}
+
+impl TryFrom<u16> for SocketDataType {
+    type Error = InvalidSocketDataType;
+
     #[inline]
-    #[track_caller]
-    pub fn from_u16(data_type: u16) -> Self {
-        match data_type {
+    fn try_from(data_type: u16) -> Result<Self, Self::Error> {
+        Ok(match data_type {
             0 => Self::None,
             1 => Self::GamePacket,
             2 => Self::FieldInfo,
@@ -46,14 +67,7 @@ impl SocketDataType {
             13 => Self::SetLoadout,
             14 => Self::InitComplete,
             15 => Self::ControllableTeamInfo,
-            _ => panic!("Invalid socket data type: {}", data_type),
-        }
-    }
-}
-
-impl From<u16> for SocketDataType {
-    #[inline]
-    fn from(data_type: u16) -> Self {
-        SocketDataType::from_u16(data_type)
+            _ => return Err(InvalidSocketDataType(data_type)),
+        })
     }
 }
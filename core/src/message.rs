@@ -0,0 +1,197 @@
+use crate::{flat, InvalidSocketDataType, SocketDataType};
+use flatbuffers::{root, FlatBufferBuilder, InvalidFlatbuffer};
+use std::{error::Error, fmt::Display, io};
+
+#[derive(Debug)]
+pub enum ProtocolError {
+    Io(io::Error),
+    InvalidDataType(InvalidSocketDataType),
+    InvalidFlatbuffer(SocketDataType, InvalidFlatbuffer),
+    PayloadTooLarge(usize),
+}
+
+impl Display for ProtocolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => e.fmt(f),
+            Self::InvalidDataType(e) => e.fmt(f),
+            Self::InvalidFlatbuffer(data_type, e) => {
+                write!(f, "Invalid flatbuffer for {data_type:?} - {e}")
+            }
+            Self::PayloadTooLarge(len) => {
+                write!(f, "Payload of {len} bytes is too large for a single frame")
+            }
+        }
+    }
+}
+
+impl Error for ProtocolError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::InvalidDataType(e) => Some(e),
+            Self::InvalidFlatbuffer(_, e) => Some(e),
+            Self::PayloadTooLarge(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for ProtocolError {
+    #[inline]
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<ProtocolError> for io::Error {
+    fn from(e: ProtocolError) -> Self {
+        match e {
+            ProtocolError::Io(e) => e,
+            e => io::Error::new(io::ErrorKind::InvalidData, e),
+        }
+    }
+}
+
+impl From<InvalidSocketDataType> for ProtocolError {
+    #[inline]
+    fn from(e: InvalidSocketDataType) -> Self {
+        Self::InvalidDataType(e)
+    }
+}
+
+/// A fully unpacked message, one variant per [`SocketDataType`]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Message {
+    None,
+    GamePacket(Box<flat::GamePacketT>),
+    FieldInfo(flat::FieldInfoT),
+    StartCommand(flat::StartCommandT),
+    MatchConfig(Box<flat::MatchConfigurationT>),
+    PlayerInput(flat::PlayerInputT),
+    DesiredGameState(flat::DesiredGameStateT),
+    RenderGroup(flat::RenderGroupT),
+    RemoveRenderGroup(flat::RemoveRenderGroupT),
+    MatchComm(flat::MatchCommT),
+    BallPrediction(flat::BallPredictionT),
+    ConnectionSettings(flat::ConnectionSettingsT),
+    StopCommand(flat::StopCommandT),
+    SetLoadout(flat::SetLoadoutT),
+    InitComplete,
+    ControllableTeamInfo(flat::ControllableTeamInfoT),
+}
+
+impl Message {
+    #[must_use]
+    pub const fn data_type(&self) -> SocketDataType {
+        match self {
+            Self::None => SocketDataType::None,
+            Self::GamePacket(_) => SocketDataType::GamePacket,
+            Self::FieldInfo(_) => SocketDataType::FieldInfo,
+            Self::StartCommand(_) => SocketDataType::StartCommand,
+            Self::MatchConfig(_) => SocketDataType::MatchConfig,
+            Self::PlayerInput(_) => SocketDataType::PlayerInput,
+            Self::DesiredGameState(_) => SocketDataType::DesiredGameState,
+            Self::RenderGroup(_) => SocketDataType::RenderGroup,
+            Self::RemoveRenderGroup(_) => SocketDataType::RemoveRenderGroup,
+            Self::MatchComm(_) => SocketDataType::MatchComm,
+            Self::BallPrediction(_) => SocketDataType::BallPrediction,
+            Self::ConnectionSettings(_) => SocketDataType::ConnectionSettings,
+            Self::StopCommand(_) => SocketDataType::StopCommand,
+            Self::SetLoadout(_) => SocketDataType::SetLoadout,
+            Self::InitComplete => SocketDataType::InitComplete,
+            Self::ControllableTeamInfo(_) => SocketDataType::ControllableTeamInfo,
+        }
+    }
+
+    /// Verifies and unpacks `bytes` as the flatbuffer that belongs to `data_type`
+    pub fn decode(data_type: SocketDataType, bytes: &[u8]) -> Result<Self, ProtocolError> {
+        let invalid = |e| ProtocolError::InvalidFlatbuffer(data_type, e);
+
+        Ok(match data_type {
+            SocketDataType::None => Self::None,
+            SocketDataType::GamePacket => Self::GamePacket(Box::new(
+                root::<flat::GamePacket>(bytes).map_err(invalid)?.unpack(),
+            )),
+            SocketDataType::FieldInfo => {
+                Self::FieldInfo(root::<flat::FieldInfo>(bytes).map_err(invalid)?.unpack())
+            }
+            SocketDataType::StartCommand => {
+                Self::StartCommand(root::<flat::StartCommand>(bytes).map_err(invalid)?.unpack())
+            }
+            SocketDataType::MatchConfig => Self::MatchConfig(Box::new(
+                root::<flat::MatchConfiguration>(bytes)
+                    .map_err(invalid)?
+                    .unpack(),
+            )),
+            SocketDataType::PlayerInput => {
+                Self::PlayerInput(root::<flat::PlayerInput>(bytes).map_err(invalid)?.unpack())
+            }
+            SocketDataType::DesiredGameState => Self::DesiredGameState(
+                root::<flat::DesiredGameState>(bytes)
+                    .map_err(invalid)?
+                    .unpack(),
+            ),
+            SocketDataType::RenderGroup => {
+                Self::RenderGroup(root::<flat::RenderGroup>(bytes).map_err(invalid)?.unpack())
+            }
+            SocketDataType::RemoveRenderGroup => Self::RemoveRenderGroup(
+                root::<flat::RemoveRenderGroup>(bytes)
+                    .map_err(invalid)?
+                    .unpack(),
+            ),
+            SocketDataType::MatchComm => {
+                Self::MatchComm(root::<flat::MatchComm>(bytes).map_err(invalid)?.unpack())
+            }
+            SocketDataType::BallPrediction => Self::BallPrediction(
+                root::<flat::BallPrediction>(bytes)
+                    .map_err(invalid)?
+                    .unpack(),
+            ),
+            SocketDataType::ConnectionSettings => Self::ConnectionSettings(
+                root::<flat::ConnectionSettings>(bytes)
+                    .map_err(invalid)?
+                    .unpack(),
+            ),
+            SocketDataType::StopCommand => {
+                Self::StopCommand(root::<flat::StopCommand>(bytes).map_err(invalid)?.unpack())
+            }
+            SocketDataType::SetLoadout => {
+                Self::SetLoadout(root::<flat::SetLoadout>(bytes).map_err(invalid)?.unpack())
+            }
+            SocketDataType::InitComplete => Self::InitComplete,
+            SocketDataType::ControllableTeamInfo => Self::ControllableTeamInfo(
+                root::<flat::ControllableTeamInfo>(bytes)
+                    .map_err(invalid)?
+                    .unpack(),
+            ),
+        })
+    }
+
+    /// Packs the message into `builder` and returns the finished bytes.
+    /// Messages without a flatbuffer payload produce an empty slice.
+    pub fn encode<'a>(&self, builder: &'a mut FlatBufferBuilder) -> &'a [u8] {
+        builder.reset();
+
+        let offset = match self {
+            Self::None | Self::InitComplete => return &[],
+            Self::GamePacket(packet) => packet.pack(builder).as_union_value(),
+            Self::FieldInfo(info) => info.pack(builder).as_union_value(),
+            Self::StartCommand(command) => command.pack(builder).as_union_value(),
+            Self::MatchConfig(settings) => settings.pack(builder).as_union_value(),
+            Self::PlayerInput(input) => input.pack(builder).as_union_value(),
+            Self::DesiredGameState(state) => state.pack(builder).as_union_value(),
+            Self::RenderGroup(group) => group.pack(builder).as_union_value(),
+            Self::RemoveRenderGroup(group) => group.pack(builder).as_union_value(),
+            Self::MatchComm(comm) => comm.pack(builder).as_union_value(),
+            Self::BallPrediction(prediction) => prediction.pack(builder).as_union_value(),
+            Self::ConnectionSettings(settings) => settings.pack(builder).as_union_value(),
+            Self::StopCommand(command) => command.pack(builder).as_union_value(),
+            Self::SetLoadout(loadout) => loadout.pack(builder).as_union_value(),
+            Self::ControllableTeamInfo(info) => info.pack(builder).as_union_value(),
+        };
+
+        builder.finish(offset, None);
+        builder.finished_data()
+    }
+}
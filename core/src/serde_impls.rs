@@ -0,0 +1,90 @@
+use crate::flat;
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Flatbuffer enums are generated as integer newtypes,
+/// so they're (de)serialized by variant name to keep the output human-readable
+macro_rules! impl_enum_serde {
+    ($($name:ident),* $(,)?) => {$(
+        impl Serialize for flat::$name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                match self.variant_name() {
+                    Some(name) => serializer.serialize_str(name),
+                    None => Err(ser::Error::custom(format!(
+                        "Invalid value for enum {} - {}",
+                        stringify!($name),
+                        self.0
+                    ))),
+                }
+            }
+        }
+
+        impl<'de> Deserialize<'de> for flat::$name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let name = String::deserialize(deserializer)?;
+
+                Self::ENUM_VALUES
+                    .iter()
+                    .copied()
+                    .find(|value| value.variant_name() == Some(name.as_str()))
+                    .ok_or_else(|| {
+                        de::Error::custom(format!(
+                            "Invalid value for enum {} - {name}",
+                            stringify!($name)
+                        ))
+                    })
+            }
+        }
+    )*};
+}
+
+// generated by build.rs with every enum in the schema
+include!("generated/serde_enums.rs");
+
+#[cfg(test)]
+mod tests {
+    use crate::flat;
+
+    #[test]
+    fn game_packet_round_trip() {
+        let mut packet = flat::GamePacketT::default();
+        packet.match_info.match_phase = flat::MatchPhase::Active;
+        packet.match_info.seconds_elapsed = 12.5;
+
+        let mut player = flat::PlayerInfoT::default();
+        player.name = "Bot".to_string();
+        player.air_state = flat::AirState::InAir;
+        player.boost = 48;
+        packet.players.push(player);
+
+        let serialized = toml::to_string(&packet).unwrap();
+        assert!(serialized.contains("match_phase = \"Active\""));
+        assert!(serialized.contains("air_state = \"InAir\""));
+
+        let deserialized: flat::GamePacketT = toml::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, packet);
+    }
+
+    #[test]
+    fn match_configuration_round_trip() {
+        let mut match_settings = flat::MatchConfigurationT::default();
+        match_settings.game_mode = flat::GameMode::Hoops;
+        match_settings.instant_start = true;
+
+        let mut mutators = flat::MutatorSettingsT::default();
+        mutators.match_length = flat::MatchLengthMutator::TenMinutes;
+        match_settings.mutators = Some(Box::new(mutators));
+
+        let serialized = toml::to_string(&match_settings).unwrap();
+        assert!(serialized.contains("game_mode = \"Hoops\""));
+        assert!(serialized.contains("match_length = \"TenMinutes\""));
+
+        let deserialized: flat::MatchConfigurationT = toml::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, match_settings);
+    }
+
+    #[test]
+    fn unknown_variant_name_is_rejected() {
+        let result = toml::from_str::<flat::MatchConfigurationT>("game_mode = \"Cricket\"");
+        assert!(result.is_err());
+    }
+}
@@ -1,63 +1,22 @@
-use rlbot_sockets::{flat, flatbuffers::FlatBufferBuilder, SocketDataType};
+use rlbot_sockets::{client, flat, Message, SocketDataType};
 use std::{
     io::{Result as IoResult, Write},
     time::{Duration, Instant},
 };
-use tokio::{
-    io::{AsyncReadExt, AsyncWriteExt},
-    net::TcpStream,
-};
 
 struct Connection {
-    tcp: TcpStream,
-    builder: FlatBufferBuilder<'static>,
-    buffer: Vec<u8>,
+    inner: client::Connection,
 }
 
 impl Connection {
     async fn new() -> IoResult<Self> {
         Ok(Self {
-            tcp: TcpStream::connect("127.0.0.1:23234").await?,
-            builder: FlatBufferBuilder::with_capacity(1024),
-            buffer: Vec::with_capacity(1024),
+            inner: client::Connection::connect("127.0.0.1:23234").await?,
         })
     }
 
-    async fn send_flatbuffer(&mut self, data_type: SocketDataType) -> IoResult<()> {
-        let flat = self.builder.finished_data();
-
-        self.buffer.clear();
-        self.buffer.reserve(4 + flat.len());
-
-        self.buffer
-            .extend_from_slice(&(data_type as u16).to_be_bytes());
-        let size = u16::try_from(flat.len()).expect("Flatbuffer too large");
-        self.buffer.extend_from_slice(&size.to_be_bytes());
-        self.buffer.extend_from_slice(flat);
-
-        self.tcp.write_all(&self.buffer).await?;
-        self.tcp.flush().await?;
-        Ok(())
-    }
-
-    async fn read_flatbuffer(&mut self) -> IoResult<SocketDataType> {
-        let data_type = self.tcp.read_u16().await?;
-        let size = self.tcp.read_u16().await?;
-
-        self.buffer.resize(usize::from(size), 0);
-        self.tcp.read_exact(&mut self.buffer).await?;
-
-        Ok(SocketDataType::from_u16(data_type))
-    }
-
     async fn wait_for_type(&mut self, data_type: SocketDataType) -> IoResult<()> {
-        loop {
-            let received_type = self.read_flatbuffer().await?;
-            if received_type == data_type {
-                break;
-            }
-        }
-
+        self.inner.wait_for(data_type).await?;
         Ok(())
     }
 
@@ -65,16 +24,13 @@ impl Connection {
         let mut connection_settings = flat::ConnectionSettingsT::default();
         connection_settings.wants_ball_predictions = true;
 
-        self.builder.reset();
-        let offset = connection_settings.pack(&mut self.builder);
-        self.builder.finish(offset, None);
-
-        self.send_flatbuffer(SocketDataType::ConnectionSettings)
+        self.inner
+            .send(&Message::ConnectionSettings(connection_settings))
             .await?;
 
         // wait for a message back
         // we don't care about the message, just that we got one
-        self.read_flatbuffer().await?;
+        self.inner.recv().await?;
 
         Ok(())
     }
@@ -84,11 +40,7 @@ impl Connection {
         let mut start = flat::StartCommandT::default();
         start.config_path = match_settings.to_string();
 
-        self.builder.reset();
-        let offset = start.pack(&mut self.builder);
-        self.builder.finish(offset, None);
-
-        self.send_flatbuffer(SocketDataType::StartCommand).await?;
+        self.inner.send(&Message::StartCommand(start)).await?;
 
         self.wait_for_type(SocketDataType::GamePacket).await?;
         self.wait_for_type(SocketDataType::BallPrediction).await?;
@@ -100,11 +52,7 @@ impl Connection {
         let mut stop_message = flat::StopCommandT::default();
         stop_message.shutdown_server = true;
 
-        self.builder.reset();
-        let offset = stop_message.pack(&mut self.builder);
-        self.builder.finish(offset, None);
-
-        self.send_flatbuffer(SocketDataType::StopCommand).await?;
+        self.inner.send(&Message::StopCommand(stop_message)).await?;
 
         self.wait_for_type(SocketDataType::None).await?;
 
@@ -4,14 +4,32 @@ mod messages;
 mod utils;
 
 use clap::{Parser, Subcommand};
-use rlbot_sockets::{flat, flatbuffers::root, SocketDataType};
-use std::{net::Ipv4Addr, path::Path, thread};
+use rlbot_sockets::{flat, FrameReader, FrameWriter, Message, ProtocolError, SocketDataType};
+use std::{
+    net::Ipv4Addr,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicU32, Ordering},
+    thread,
+    time::Duration,
+};
+use thiserror::Error;
 use tokio::{
-    io::{AsyncReadExt, AsyncWriteExt, Result as IoResult},
-    net::{TcpListener, TcpStream},
-    sync::{broadcast, mpsc, oneshot},
+    io::Result as IoResult,
+    net::{
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+        TcpListener, TcpStream,
+    },
+    sync::{
+        broadcast::{self, error::RecvError},
+        mpsc, oneshot,
+    },
+};
+use utils::{
+    admin, game,
+    lockstep::{Lockstep, TimeoutPolicy},
+    parse::file_to_match_settings,
 };
-use utils::{game, parse::file_to_match_settings};
 
 const RLVISER_PATH: &str = if cfg!(windows) {
     "./rlviser.exe"
@@ -23,6 +41,8 @@ const RLBOT_PORT: u16 = 23234;
 const RLVISER_PORT: u16 = 23235;
 const ROCKETSIM_PORT: u16 = 23236;
 
+static NEXT_CLIENT_ID: AtomicU32 = AtomicU32::new(0);
+
 fn valid_path(s: &str) -> Result<String, String> {
     if Path::new(s).exists() {
         Ok(s.to_string())
@@ -31,6 +51,13 @@ fn valid_path(s: &str) -> Result<String, String> {
     }
 }
 
+fn valid_game_speed(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(speed) if speed.is_finite() && speed > 0. => Ok(speed),
+        _ => Err(format!("`{s}` is not a positive number")),
+    }
+}
+
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
 struct Cli {
@@ -38,6 +65,34 @@ struct Cli {
     commands: Option<Commands>,
     #[arg(long, value_parser = clap::value_parser!(u16).range(1..), default_value_t = RLBOT_PORT)]
     rlbot_port: u16,
+    /// Only step the simulation once every connected bot has sent an input for the current tick
+    #[arg(long)]
+    lockstep: bool,
+    /// How long to wait for bots each tick in lockstep mode, in milliseconds
+    #[arg(long, default_value_t = 1000, requires = "lockstep")]
+    lockstep_timeout: u64,
+    /// What to do with bots that don't send an input before the timeout
+    #[arg(long, value_enum, default_value_t, requires = "lockstep")]
+    lockstep_policy: TimeoutPolicy,
+    /// Seed for kickoff spawns, overrides the `seed` in the match config
+    #[arg(long)]
+    seed: Option<u32>,
+    /// Record the inputs of the latest match to this file so it can be re-simulated with `replay`.
+    /// The file is overwritten whenever a new match starts
+    #[arg(long)]
+    record: Option<PathBuf>,
+    /// Log every packet sent to bots to this file so it can be served again with `playback`
+    #[arg(long)]
+    log_packets: Option<PathBuf>,
+    /// Write a CSV row for every car on every tick to this file
+    #[arg(long)]
+    telemetry: Option<PathBuf>,
+    /// How many seconds of the match to keep in memory for the `rewind` command
+    #[arg(long, default_value_t = game::DEFAULT_REWIND_SECONDS)]
+    rewind_seconds: u32,
+    /// Where to save snapshots requested with SIGUSR1 or the `snapshot` command
+    #[arg(long, default_value = "snapshot.rlss")]
+    snapshot_path: PathBuf,
 }
 
 #[derive(Subcommand)]
@@ -51,7 +106,27 @@ pub enum Commands {
         #[arg(long, value_parser = clap::value_parser!(u16).range(1..), default_value_t = ROCKETSIM_PORT)]
         rocketsim_port: u16,
     },
-    Headless,
+    Headless {
+        /// Multiplier for how fast the simulation runs compared to real time
+        #[arg(long, value_parser = valid_game_speed, default_value_t = 1.)]
+        game_speed: f32,
+        /// Run the simulation as fast as possible
+        #[arg(long, conflicts_with = "game_speed")]
+        uncapped: bool,
+    },
+    /// Serve a file made with `--log-packets` to bots without running the simulation
+    Playback {
+        #[arg(value_parser = valid_path)]
+        path: String,
+        /// Also show the logged packets in RLViser
+        #[arg(long)]
+        rlviser: bool,
+    },
+    /// Re-simulate a file made with `--record` and check that it plays out the same way
+    Replay {
+        #[arg(value_parser = valid_path)]
+        path: String,
+    },
 }
 
 impl Default for Commands {
@@ -68,6 +143,14 @@ impl Default for Commands {
 async fn main() -> IoResult<()> {
     let cli = Cli::parse();
 
+    if let Some(Commands::Replay { path }) = &cli.commands {
+        if !game::run_replay(Path::new(path))? {
+            process::exit(1);
+        }
+
+        return Ok(());
+    }
+
     let (game_tx_hold, _) = broadcast::channel(63);
     let (tx, game_rx) = mpsc::channel(31);
     let (shutdown_sender, mut shutdown_receiver) = mpsc::channel(1);
@@ -81,6 +164,13 @@ async fn main() -> IoResult<()> {
         );
     }
 
+    let lockstep = cli.lockstep.then(|| {
+        Lockstep::new(
+            Duration::from_millis(cli.lockstep_timeout),
+            cli.lockstep_policy,
+        )
+    });
+
     thread::spawn(move || {
         game::run_rl(
             game_tx,
@@ -88,9 +178,22 @@ async fn main() -> IoResult<()> {
             shutdown_sender,
             cli.rlbot_port,
             cli.commands.unwrap_or_default(),
+            game::GameOptions {
+                lockstep,
+                seed: cli.seed,
+                record_path: cli.record,
+                packet_log_path: cli.log_packets,
+                telemetry_path: cli.telemetry,
+                rewind_seconds: cli.rewind_seconds,
+            },
         )
     });
 
+    tokio::spawn(log_demolitions(game_tx_hold.subscribe()));
+    tokio::spawn(admin::run_console(tx.clone(), cli.snapshot_path.clone()));
+    #[cfg(unix)]
+    tokio::spawn(admin::snapshot_on_signal(tx.clone(), cli.snapshot_path));
+
     let tcp_connection = TcpListener::bind((Ipv4Addr::new(0, 0, 0, 0), cli.rlbot_port)).await?;
     println!("Server listening on port {}", cli.rlbot_port);
 
@@ -99,8 +202,9 @@ async fn main() -> IoResult<()> {
             biased;
             Ok((client, _)) = tcp_connection.accept() => {
                 client.set_nodelay(true)?;
-                let client_session = ClientSession::new(client, tx.clone(), game_tx_hold.subscribe());
+                let (tx, rx) = (tx.clone(), game_tx_hold.subscribe());
                 tokio::spawn(async move {
+                    let client_session = ClientSession::new(client, tx, rx).await;
                     if let Err(e) = client_session.handle_connection().await {
                         println!("Error from client connection: {e}");
                     }
@@ -115,209 +219,236 @@ async fn main() -> IoResult<()> {
     Ok(())
 }
 
+/// Logs the demolitions reported by the game thread
+async fn log_demolitions(mut rx: broadcast::Receiver<messages::FromGame>) {
+    loop {
+        match rx.recv().await {
+            Ok(messages::FromGame::Demolition(demolition)) => println!(
+                "Player {} demolished player {}",
+                demolition.attacker, demolition.victim
+            ),
+            Ok(_) | Err(RecvError::Lagged(_)) => {}
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+enum ClientError {
+    #[error(transparent)]
+    Protocol(#[from] ProtocolError),
+    #[error("Game thread is no longer running")]
+    GameClosed,
+}
+
 struct ClientSession {
-    client: TcpStream,
-    tx: mpsc::Sender<messages::ToGame>,
+    id: messages::ClientId,
+    reader: FrameReader<OwnedReadHalf>,
+    writer: FrameWriter<OwnedWriteHalf>,
+    tx: mpsc::Sender<messages::Envelope>,
     rx: broadcast::Receiver<messages::FromGame>,
+    /// Receives the reason if the game thread wants this client gone
+    kicked: mpsc::Receiver<String>,
     client_params: Option<flat::ConnectionSettingsT>,
-    buffer: Vec<u8>,
 }
 
 impl ClientSession {
-    #[inline]
-    fn new(
+    async fn new(
         client: TcpStream,
-        tx: mpsc::Sender<messages::ToGame>,
+        tx: mpsc::Sender<messages::Envelope>,
         rx: broadcast::Receiver<messages::FromGame>,
     ) -> Self {
+        let (reader, writer) = client.into_split();
+        let id = messages::ClientId(NEXT_CLIENT_ID.fetch_add(1, Ordering::Relaxed));
+
+        let (kick, kicked) = mpsc::channel(1);
+        // if the game thread is gone, the first message sent to it ends the session
+        let _ = tx
+            .send(messages::ToGame::ClientConnected(id, kick).into())
+            .await;
+
         Self {
-            client,
+            id,
+            reader: FrameReader::new(reader),
+            writer: FrameWriter::new(writer),
             tx,
             rx,
+            kicked,
             client_params: None,
-            buffer: Vec::with_capacity(1024),
         }
     }
 
-    async fn handle_connection(mut self) -> IoResult<()> {
+    async fn handle_connection(mut self) -> Result<(), ClientError> {
         loop {
             tokio::select! {
                 biased;
                 Ok(msg) = self.rx.recv() => {
-                    if !self.handle_game_message(msg).await.is_ok_and(|x| x) {
-                        break;
+                    match self.handle_game_messages(msg).await {
+                        Ok(true) => {}
+                        Ok(false) => break,
+                        Err(e) => {
+                            println!("Error sending message to client: {e}");
+                            break;
+                        }
                     }
                 }
-                Ok(data_type) = self.client.read_u16() => {
-                    if !self.handle_client_message(data_type).await.is_ok_and(|x| x) {
-                        break;
+                msg = self.reader.read_message() => {
+                    let result = match msg {
+                        Ok(msg) => self.handle_client_message(msg).await,
+                        Err(e) => Err(e.into()),
+                    };
+
+                    match result {
+                        Ok(true) => {}
+                        Ok(false) => break,
+                        Err(e) => {
+                            println!("Disconnecting client: {e}");
+                            break;
+                        }
                     }
                 }
+                Some(reason) = self.kicked.recv() => {
+                    println!("Disconnecting client: {reason}");
+                    break;
+                }
                 else => break,
             }
         }
 
         println!("Client exiting loop and closing connection");
-        self.buffered_send_flat(SocketDataType::None, &[1]).await?;
+        let _ = self
+            .tx
+            .send(messages::ToGame::ClientDisconnected(self.id).into())
+            .await;
+        self.writer.send_frame(SocketDataType::None, &[1]).await?;
 
         Ok(())
     }
 
-    async fn buffered_send_flat(&mut self, data_type: SocketDataType, flat: &[u8]) -> IoResult<()> {
-        self.buffer.clear();
-        self.buffer.reserve(4 + flat.len());
+    async fn send_to_game(&self, msg: messages::ToGame) -> Result<(), ClientError> {
+        let envelope = messages::Envelope {
+            client: Some(self.id),
+            msg,
+        };
 
-        self.buffer
-            .extend_from_slice(&(data_type as u16).to_be_bytes());
-        let size = u16::try_from(flat.len()).expect("Flatbuffer too large");
-        self.buffer.extend_from_slice(&size.to_be_bytes());
-        self.buffer.extend_from_slice(flat);
-
-        self.client.write_all(&self.buffer).await?;
-        self.client.flush().await?;
-        Ok(())
+        self.tx
+            .send(envelope)
+            .await
+            .map_err(|_| ClientError::GameClosed)
     }
 
-    async fn handle_client_message(&mut self, data_type: u16) -> IoResult<bool> {
-        let size = self.client.read_u16().await?;
-
-        self.buffer.resize(usize::from(size), 0);
-        self.client.read_exact(&mut self.buffer).await?;
-
-        match SocketDataType::from_u16(data_type) {
-            SocketDataType::None => {
+    async fn handle_client_message(&mut self, msg: Message) -> Result<bool, ClientError> {
+        match msg {
+            Message::None => {
                 println!("Received None message type, closing connection");
                 return Ok(false);
             }
-            SocketDataType::MatchConfig => {
-                let match_settings = root::<flat::MatchConfiguration>(&self.buffer)
-                    .unwrap()
-                    .unpack();
-                self.tx
-                    .blocking_send(messages::ToGame::MatchSettings(match_settings))
-                    .unwrap();
+            Message::MatchConfig(match_settings) => {
+                self.send_to_game(messages::ToGame::MatchSettings(
+                    *match_settings,
+                    messages::ExtraMatchSettings::default(),
+                ))
+                .await?;
             }
-            SocketDataType::ConnectionSettings => {
-                let connection_settings = root::<flat::ConnectionSettings>(&self.buffer)
-                    .unwrap()
-                    .unpack();
-
+            Message::ConnectionSettings(connection_settings) => {
                 let agent_id = connection_settings.agent_id.clone();
                 self.client_params.replace(connection_settings);
 
                 let (match_settings_tx, match_settings_rx) = oneshot::channel();
-                self.tx
-                    .send(messages::ToGame::MatchSettingsRequest(match_settings_tx))
-                    .await
-                    .unwrap();
+                self.send_to_game(messages::ToGame::MatchSettingsRequest(match_settings_tx))
+                    .await?;
 
                 if let Ok(match_settings_flat) = match_settings_rx.await {
-                    self.buffered_send_flat(SocketDataType::MatchConfig, &match_settings_flat)
-                        .await?;
+                    self.writer
+                        .queue_frame(SocketDataType::MatchConfig, &match_settings_flat)?;
                 }
 
                 let (field_info_tx, field_info_rx) = oneshot::channel();
-                self.tx
-                    .send(messages::ToGame::FieldInfoRequest(field_info_tx))
-                    .await
-                    .unwrap();
+                self.send_to_game(messages::ToGame::FieldInfoRequest(field_info_tx))
+                    .await?;
 
                 if let Ok(field_info_flat) = field_info_rx.await {
-                    self.buffered_send_flat(SocketDataType::FieldInfo, &field_info_flat)
-                        .await?;
+                    self.writer
+                        .queue_frame(SocketDataType::FieldInfo, &field_info_flat)?;
                 }
 
                 let (controllable_team_info_tx, controllable_team_info_rx) = oneshot::channel();
-                self.tx
-                    .send(messages::ToGame::ControllableTeamInfoRequest(
-                        agent_id,
-                        controllable_team_info_tx,
-                    ))
-                    .await
-                    .unwrap();
+                self.send_to_game(messages::ToGame::ControllableTeamInfoRequest(
+                    self.id,
+                    agent_id,
+                    controllable_team_info_tx,
+                ))
+                .await?;
 
                 if let Ok(Some(controllable_team_info_flat)) = controllable_team_info_rx.await {
-                    self.buffered_send_flat(
+                    self.writer.queue_frame(
                         SocketDataType::ControllableTeamInfo,
                         &controllable_team_info_flat,
-                    )
-                    .await?;
+                    )?;
                 }
-            }
-            SocketDataType::StartCommand => {
-                let start_command = root::<flat::StartCommand>(&self.buffer).unwrap().unpack();
 
+                self.writer.flush().await?;
+            }
+            Message::StartCommand(start_command) => {
                 match file_to_match_settings(start_command.config_path).await {
-                    Ok(match_settings) => {
-                        self.tx
-                            .send(messages::ToGame::MatchSettings(match_settings))
-                            .await
-                            .unwrap();
+                    Ok((match_settings, extra)) => {
+                        self.send_to_game(messages::ToGame::MatchSettings(match_settings, extra))
+                            .await?;
                     }
                     Err(e) => {
                         println!("Error reading match settings: {e}");
                     }
                 }
             }
-            SocketDataType::PlayerInput => {
-                let input = root::<flat::PlayerInput>(&self.buffer).unwrap().unpack();
-                self.tx
-                    .send(messages::ToGame::PlayerInput(input))
-                    .await
-                    .unwrap();
+            Message::PlayerInput(input) => {
+                self.send_to_game(messages::ToGame::PlayerInput(input))
+                    .await?;
             }
-            SocketDataType::DesiredGameState => {
-                let desired_state = root::<flat::DesiredGameState>(&self.buffer)
-                    .unwrap()
-                    .unpack();
-                self.tx
-                    .send(messages::ToGame::DesiredGameState(desired_state))
-                    .await
-                    .unwrap();
+            Message::DesiredGameState(desired_state) => {
+                self.send_to_game(messages::ToGame::DesiredGameState(desired_state))
+                    .await?;
             }
-            SocketDataType::RenderGroup => {
-                let group = root::<flat::RenderGroup>(&self.buffer).unwrap().unpack();
-                self.tx
-                    .send(messages::ToGame::RenderGroup(group))
-                    .await
-                    .unwrap();
+            Message::RenderGroup(group) => {
+                self.send_to_game(messages::ToGame::RenderGroup(group))
+                    .await?;
             }
-            SocketDataType::RemoveRenderGroup => {
-                let group = root::<flat::RemoveRenderGroup>(&self.buffer)
-                    .unwrap()
-                    .unpack();
-                self.tx
-                    .send(messages::ToGame::RemoveRenderGroup(group))
-                    .await
-                    .unwrap();
+            Message::RemoveRenderGroup(group) => {
+                self.send_to_game(messages::ToGame::RemoveRenderGroup(group))
+                    .await?;
             }
-            SocketDataType::MatchComm => {
-                // assert that it's actually a MatchComm message
-                assert!(root::<flat::MatchComm>(&self.buffer).is_ok());
-
-                self.tx
-                    .send(messages::ToGame::MatchComm(
-                        self.buffer.clone().into_boxed_slice(),
-                    ))
-                    .await
-                    .unwrap();
+            Message::MatchComm(comm) => {
+                self.send_to_game(messages::ToGame::MatchComm(comm)).await?;
             }
-            SocketDataType::StopCommand => {
-                let command = root::<flat::StopCommand>(&self.buffer).unwrap().unpack();
-                self.tx
-                    .send(messages::ToGame::StopCommand(command))
-                    .await
-                    .unwrap();
+            Message::StopCommand(command) => {
+                self.send_to_game(messages::ToGame::StopCommand(command))
+                    .await?;
             }
-            i => {
-                println!("Received message type: {i:?}");
+            msg => {
+                println!("Received message type: {:?}", msg.data_type());
             }
         }
 
         Ok(true)
     }
 
-    async fn handle_game_message(&mut self, msg: messages::FromGame) -> IoResult<bool> {
+    /// Queues `msg` along with any other messages that are already waiting,
+    /// then sends them all to the client in a single write
+    async fn handle_game_messages(&mut self, msg: messages::FromGame) -> Result<bool, ClientError> {
+        let mut keep_going = self.queue_game_message(msg)?;
+
+        while keep_going {
+            let Ok(msg) = self.rx.try_recv() else {
+                break;
+            };
+
+            keep_going = self.queue_game_message(msg)?;
+        }
+
+        self.writer.flush().await?;
+        Ok(keep_going)
+    }
+
+    fn queue_game_message(&mut self, msg: messages::FromGame) -> Result<bool, ClientError> {
         match msg {
             messages::FromGame::StopCommand(force) => {
                 return Ok(force
@@ -328,17 +459,16 @@ impl ClientSession {
             }
             messages::FromGame::GameTickPacket(packet) => {
                 if self.client_params.is_some() {
-                    self.buffered_send_flat(SocketDataType::GamePacket, &packet)
-                        .await?;
+                    self.writer
+                        .queue_frame(SocketDataType::GamePacket, &packet)?;
                 }
             }
             messages::FromGame::MatchSettings(settings) => {
-                self.buffered_send_flat(SocketDataType::MatchConfig, &settings)
-                    .await?;
+                self.writer
+                    .queue_frame(SocketDataType::MatchConfig, &settings)?;
             }
             messages::FromGame::FieldInfo(field) => {
-                self.buffered_send_flat(SocketDataType::FieldInfo, &field)
-                    .await?;
+                self.writer.queue_frame(SocketDataType::FieldInfo, &field)?;
             }
             messages::FromGame::MatchComm(message) => {
                 let Some(client_params) = &self.client_params else {
@@ -346,8 +476,8 @@ impl ClientSession {
                 };
 
                 if client_params.wants_comms {
-                    self.buffered_send_flat(SocketDataType::MatchComm, &message)
-                        .await?;
+                    self.writer
+                        .queue_frame(SocketDataType::MatchComm, &message)?;
                 }
             }
             messages::FromGame::BallPrediction(prediction) => {
@@ -356,10 +486,12 @@ impl ClientSession {
                 };
 
                 if client_params.wants_ball_predictions {
-                    self.buffered_send_flat(SocketDataType::BallPrediction, &prediction)
-                        .await?;
+                    self.writer
+                        .queue_frame(SocketDataType::BallPrediction, &prediction)?;
                 }
             }
+            // demolitions already reach clients through the game packet
+            messages::FromGame::Demolition(_) => {}
         }
 
         Ok(true)
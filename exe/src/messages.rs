@@ -1,18 +1,66 @@
 use rlbot_sockets::flat;
-use tokio::sync::oneshot;
+use std::path::PathBuf;
+use tokio::sync::{mpsc, oneshot};
+
+/// Match options from a config file that `MatchConfigurationT` has no place for
+#[derive(Debug, Default)]
+pub struct ExtraMatchSettings {
+    pub seed: Option<u32>,
+    /// Start the match from this snapshot instead of a kickoff
+    pub snapshot: Option<PathBuf>,
+    /// How many balls to play with, one if not set
+    pub ball_count: Option<u32>,
+}
+
+/// How far back a `rewind` command goes
+#[derive(Clone, Copy, Debug)]
+pub enum RewindTarget {
+    SecondsAgo(f32),
+    Tick(u64),
+}
+
+/// Identifies a connected client to the game thread
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ClientId(pub u32);
 
 #[derive(Debug)]
 pub enum ToGame {
+    /// The game thread sends the reason on the channel if it wants the client disconnected
+    ClientConnected(ClientId, mpsc::Sender<String>),
+    ClientDisconnected(ClientId),
     FieldInfoRequest(oneshot::Sender<Box<[u8]>>),
     MatchSettingsRequest(oneshot::Sender<Box<[u8]>>),
-    MatchSettings(flat::MatchConfigurationT),
+    MatchSettings(flat::MatchConfigurationT, ExtraMatchSettings),
     PlayerInput(flat::PlayerInputT),
     DesiredGameState(flat::DesiredGameStateT),
     RenderGroup(flat::RenderGroupT),
     RemoveRenderGroup(flat::RemoveRenderGroupT),
-    MatchComm(Box<[u8]>),
+    MatchComm(flat::MatchCommT),
     StopCommand(flat::StopCommandT),
-    ControllableTeamInfoRequest(String, oneshot::Sender<Option<Box<[u8]>>>),
+    ControllableTeamInfoRequest(ClientId, String, oneshot::Sender<Option<Box<[u8]>>>),
+    SaveSnapshot(PathBuf),
+    Rewind(RewindTarget),
+}
+
+/// A message for the game thread, with the client that sent it.
+/// Messages from the server's own console don't come from a client.
+#[derive(Debug)]
+pub struct Envelope {
+    pub client: Option<ClientId>,
+    pub msg: ToGame,
+}
+
+impl From<ToGame> for Envelope {
+    fn from(msg: ToGame) -> Self {
+        Self { client: None, msg }
+    }
+}
+
+/// Player indices of a demolition that happened in the game thread
+#[derive(Clone, Copy, Debug)]
+pub struct Demolition {
+    pub attacker: u32,
+    pub victim: u32,
 }
 
 #[derive(Clone, Debug)]
@@ -23,4 +71,5 @@ pub enum FromGame {
     FieldInfo(Box<[u8]>),
     MatchComm(Box<[u8]>),
     BallPrediction(Box<[u8]>),
+    Demolition(Demolition),
 }
@@ -0,0 +1,83 @@
+use crate::messages::{Envelope, RewindTarget, ToGame};
+use std::path::PathBuf;
+use tokio::{
+    io::{stdin, AsyncBufReadExt, BufReader},
+    sync::mpsc,
+};
+
+const HELP: &str = "Commands:
+  snapshot [path]  save the current arena state, to the --snapshot-path if no path is given
+  rewind <seconds> go back to how the match was this many seconds ago
+  rewind tick <n>  go back to tick n, if it's still buffered
+  help             show this message";
+
+/// Reads commands typed into the server's terminal until stdin is closed
+pub async fn run_console(tx: mpsc::Sender<Envelope>, snapshot_path: PathBuf) {
+    let mut lines = BufReader::new(stdin()).lines();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        let mut args = line.split_whitespace();
+
+        let msg = match args.next() {
+            None => continue,
+            Some("snapshot") => ToGame::SaveSnapshot(
+                args.next()
+                    .map_or_else(|| snapshot_path.clone(), PathBuf::from),
+            ),
+            Some("rewind") => {
+                let target = match (args.next(), args.next()) {
+                    (Some("tick"), Some(tick)) => tick.parse().ok().map(RewindTarget::Tick),
+                    (Some(seconds), None) => seconds
+                        .parse()
+                        .ok()
+                        .filter(|seconds: &f32| seconds.is_finite() && *seconds >= 0.)
+                        .map(RewindTarget::SecondsAgo),
+                    _ => None,
+                };
+
+                let Some(target) = target else {
+                    println!("Usage: `rewind <seconds>` or `rewind tick <n>`");
+                    continue;
+                };
+
+                ToGame::Rewind(target)
+            }
+            Some("help") => {
+                println!("{HELP}");
+                continue;
+            }
+            Some(command) => {
+                println!("Unknown command `{command}`, type `help` for a list of commands");
+                continue;
+            }
+        };
+
+        if tx.send(msg.into()).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Saves a snapshot every time the server receives SIGUSR1
+#[cfg(unix)]
+pub async fn snapshot_on_signal(tx: mpsc::Sender<Envelope>, snapshot_path: PathBuf) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut signals = match signal(SignalKind::user_defined1()) {
+        Ok(signals) => signals,
+        Err(e) => {
+            println!("Failed to listen for SIGUSR1: {e}");
+            return;
+        }
+    };
+
+    while signals.recv().await.is_some() {
+        if tx
+            .send(ToGame::SaveSnapshot(snapshot_path.clone()).into())
+            .await
+            .is_err()
+        {
+            break;
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::messages::ClientId;
 use rlbot_sockets::flat;
 
 struct PlayerMetadata {
@@ -5,7 +6,7 @@ struct PlayerMetadata {
     spawn_id: i32,
     team: u32,
     agent_id: String,
-    is_reserved: bool,
+    reserved_by: Option<ClientId>,
 }
 
 #[derive(Default)]
@@ -30,7 +31,7 @@ impl AgentReservation {
                         spawn_id: player.spawn_id,
                         team: player.team,
                         agent_id: player.agent_id.clone(),
-                        is_reserved: false,
+                        reserved_by: None,
                     })
                 }
                 _ => continue,
@@ -38,12 +39,16 @@ impl AgentReservation {
         }
     }
 
-    pub fn reserve_player(&mut self, agent_id: &str) -> Option<flat::ControllableTeamInfoT> {
+    pub fn reserve_player(
+        &mut self,
+        client: ClientId,
+        agent_id: &str,
+    ) -> Option<flat::ControllableTeamInfoT> {
         let player = self
             .known_players
             .iter_mut()
-            .find(|p| !p.is_reserved && p.agent_id == agent_id)?;
-        player.is_reserved = true;
+            .find(|p| p.reserved_by.is_none() && p.agent_id == agent_id)?;
+        player.reserved_by = Some(client);
 
         let mut controllable_info = flat::ControllableInfoT::default();
         controllable_info.index = player.index;
@@ -55,4 +60,23 @@ impl AgentReservation {
 
         Some(team_controllable_info)
     }
+
+    pub fn reserved_indices(&self) -> impl Iterator<Item = u32> + '_ {
+        self.known_players
+            .iter()
+            .filter(|p| p.reserved_by.is_some())
+            .map(|p| p.index)
+    }
+
+    /// Frees the players that `client` reserved, returning their indices
+    pub fn release(&mut self, client: ClientId) -> Vec<u32> {
+        self.known_players
+            .iter_mut()
+            .filter(|p| p.reserved_by == Some(client))
+            .map(|p| {
+                p.reserved_by = None;
+                p.index
+            })
+            .collect()
+    }
 }
@@ -0,0 +1,69 @@
+use rlbot_sockets::flat;
+
+/// Tracks regulation time and overtime for a single match
+#[derive(Clone)]
+pub struct MatchClock {
+    is_unlimited: bool,
+    remaining: f32,
+    is_overtime: bool,
+}
+
+impl Default for MatchClock {
+    fn default() -> Self {
+        Self::new(flat::MatchLengthMutator::Unlimited)
+    }
+}
+
+impl MatchClock {
+    pub fn new(match_length: flat::MatchLengthMutator) -> Self {
+        let minutes = match match_length {
+            flat::MatchLengthMutator::TenMinutes => 10.,
+            flat::MatchLengthMutator::TwentyMinutes => 20.,
+            flat::MatchLengthMutator::Unlimited => 0.,
+            _ => 5.,
+        };
+
+        Self {
+            is_unlimited: match_length == flat::MatchLengthMutator::Unlimited,
+            remaining: minutes * 60.,
+            is_overtime: false,
+        }
+    }
+
+    #[inline]
+    pub const fn is_unlimited(&self) -> bool {
+        self.is_unlimited
+    }
+
+    #[inline]
+    pub const fn is_overtime(&self) -> bool {
+        self.is_overtime
+    }
+
+    #[inline]
+    pub const fn remaining(&self) -> f32 {
+        self.remaining
+    }
+
+    /// True once regulation time has run out
+    #[inline]
+    pub fn is_expired(&self) -> bool {
+        !self.is_unlimited && self.remaining <= 0.
+    }
+
+    #[inline]
+    pub fn start_overtime(&mut self) {
+        self.is_overtime = true;
+    }
+
+    /// Counts down regulation time,
+    /// returning true on the tick that the clock runs out
+    pub fn tick(&mut self, dt: f32) -> bool {
+        if self.is_unlimited || self.is_overtime || self.remaining <= 0. {
+            return false;
+        }
+
+        self.remaining = (self.remaining - dt).max(0.);
+        self.remaining == 0.
+    }
+}
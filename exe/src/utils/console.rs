@@ -0,0 +1,174 @@
+use crate::utils::game::GAME_DT;
+use rocketsim_rs::sim::MutatorConfig;
+
+const BOOST_USED_PER_SECOND: f32 = 100. / 3.;
+const SPAWN_BOOST_AMOUNT: f32 = 100. / 3.;
+const SLOW_RECHARGE_PER_SECOND: f32 = 10.;
+const RAPID_RECHARGE_PER_SECOND: f32 = 20.;
+
+/// The boost options from Rocket League's mutator menu
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum BoostOption {
+    #[default]
+    Normal,
+    Unlimited,
+    SlowRecharge,
+    RapidRecharge,
+    NoBoost,
+}
+
+impl BoostOption {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "normal" => Some(Self::Normal),
+            "unlimited" => Some(Self::Unlimited),
+            "slowrecharge" => Some(Self::SlowRecharge),
+            "rapidrecharge" => Some(Self::RapidRecharge),
+            "none" | "noboost" => Some(Self::NoBoost),
+            _ => None,
+        }
+    }
+
+    /// RocketSim's mutators only cover how much boost is used and how much cars spawn with,
+    /// the rest is done every tick with [`BoostOption::tick`]
+    pub fn apply(self, mutators: &mut MutatorConfig) {
+        mutators.boost_used_per_second = BOOST_USED_PER_SECOND;
+        mutators.car_spawn_boost_amount = SPAWN_BOOST_AMOUNT;
+
+        match self {
+            Self::Normal | Self::SlowRecharge | Self::RapidRecharge => {}
+            Self::Unlimited => {
+                mutators.boost_used_per_second = 0.;
+                mutators.car_spawn_boost_amount = 100.;
+            }
+            Self::NoBoost => mutators.car_spawn_boost_amount = 0.,
+        }
+    }
+
+    /// Whether [`BoostOption::tick`] has to be called for every car
+    pub const fn changes_boost(self) -> bool {
+        matches!(
+            self,
+            Self::SlowRecharge | Self::RapidRecharge | Self::NoBoost
+        )
+    }
+
+    /// A car's boost after a tick, recharging while it isn't boosting.
+    /// Boost pads still fill up cars with no boost, so it's taken away again.
+    pub fn tick(self, boost: f32, is_boosting: bool) -> f32 {
+        let recharge = match self {
+            Self::SlowRecharge => SLOW_RECHARGE_PER_SECOND,
+            Self::RapidRecharge => RAPID_RECHARGE_PER_SECOND,
+            Self::NoBoost => return 0.,
+            Self::Normal | Self::Unlimited => return boost,
+        };
+
+        if is_boosting {
+            boost
+        } else {
+            (boost + recharge * GAME_DT).min(100.)
+        }
+    }
+
+    /// What cars already on the field should have their boost set to, if anything
+    pub const fn refill(self) -> Option<f32> {
+        match self {
+            Self::Unlimited => Some(100.),
+            Self::NoBoost => Some(0.),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConsoleCommand {
+    Paused(bool),
+    GameSpeed(f32),
+    Boost(BoostOption),
+    ResetKickoff,
+}
+
+impl ConsoleCommand {
+    /// Commands are case-insensitive like in Rocket League's console.
+    /// `None` if the command isn't supported.
+    pub fn parse(command: &str) -> Option<Self> {
+        let command = command.to_ascii_lowercase();
+        let args = command.split_whitespace().collect::<Vec<_>>();
+
+        match args.as_slice() {
+            ["pause"] => Some(Self::Paused(true)),
+            ["unpause"] => Some(Self::Paused(false)),
+            ["slomo", speed] | ["set", "worldinfo", "timedilation", speed] => speed
+                .parse()
+                .ok()
+                .filter(|speed: &f32| speed.is_finite() && *speed > 0.)
+                .map(Self::GameSpeed),
+            ["boost", option] => BoostOption::parse(option).map(Self::Boost),
+            ["resetkickoff"] => Some(Self::ResetKickoff),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            ConsoleCommand::parse("pause"),
+            Some(ConsoleCommand::Paused(true))
+        );
+        assert_eq!(
+            ConsoleCommand::parse("Unpause"),
+            Some(ConsoleCommand::Paused(false))
+        );
+        assert_eq!(
+            ConsoleCommand::parse("  resetkickoff "),
+            Some(ConsoleCommand::ResetKickoff)
+        );
+        assert_eq!(
+            ConsoleCommand::parse("slomo 0.5"),
+            Some(ConsoleCommand::GameSpeed(0.5))
+        );
+        assert_eq!(
+            ConsoleCommand::parse("Set WorldInfo TimeDilation 2"),
+            Some(ConsoleCommand::GameSpeed(2.))
+        );
+        assert_eq!(
+            ConsoleCommand::parse("boost RapidRecharge"),
+            Some(ConsoleCommand::Boost(BoostOption::RapidRecharge))
+        );
+        assert_eq!(
+            ConsoleCommand::parse("boost none"),
+            Some(ConsoleCommand::Boost(BoostOption::NoBoost))
+        );
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert_eq!(ConsoleCommand::parse(""), None);
+        assert_eq!(ConsoleCommand::parse("fly"), None);
+        assert_eq!(ConsoleCommand::parse("pause now"), None);
+        assert_eq!(ConsoleCommand::parse("slomo"), None);
+        assert_eq!(ConsoleCommand::parse("slomo fast"), None);
+        assert_eq!(ConsoleCommand::parse("slomo 0"), None);
+        assert_eq!(ConsoleCommand::parse("slomo -1"), None);
+        assert_eq!(ConsoleCommand::parse("slomo inf"), None);
+        assert_eq!(ConsoleCommand::parse("boost infinite"), None);
+    }
+
+    #[test]
+    fn boost_ticks() {
+        assert_eq!(BoostOption::Normal.tick(50., false), 50.);
+        assert_eq!(BoostOption::NoBoost.tick(12., false), 0.);
+
+        let recharged = BoostOption::SlowRecharge.tick(50., false);
+        assert_eq!(recharged, 50. + SLOW_RECHARGE_PER_SECOND * GAME_DT);
+        assert!(BoostOption::RapidRecharge.tick(50., false) > recharged);
+
+        assert_eq!(BoostOption::RapidRecharge.tick(50., true), 50.);
+        assert_eq!(BoostOption::RapidRecharge.tick(99.99, false), 100.);
+    }
+}
@@ -97,6 +97,17 @@ impl FlatToRs<Vec3> for flat::Vector3T {
     }
 }
 
+impl FlatToRs<RotMat> for flat::RotatorT {
+    fn to_rs(self) -> RotMat {
+        Angle {
+            pitch: self.pitch,
+            yaw: self.yaw,
+            roll: self.roll,
+        }
+        .to_rotmat()
+    }
+}
+
 impl FlatToRs<Color> for flat::ColorT {
     fn to_rs(self) -> Color {
         Color::rgba(
@@ -108,24 +119,24 @@ impl FlatToRs<Color> for flat::ColorT {
     }
 }
 
-impl FlatToRs<Render> for flat::RenderMessageT {
-    fn to_rs(self) -> Render {
+/// `None` for render types that RLViser can't draw
+impl FlatToRs<Option<Render>> for flat::RenderMessageT {
+    fn to_rs(self) -> Option<Render> {
         match self.variety {
-            flat::RenderTypeT::NONE => panic!("Invalid render type NONE"),
-            flat::RenderTypeT::Line3D(line) => Render::Line {
+            flat::RenderTypeT::Line3D(line) => Some(Render::Line {
                 start: line.start.world.to_rs(),
                 end: line.end.world.to_rs(),
                 color: line.color.to_rs(),
-            },
+            }),
             flat::RenderTypeT::PolyLine3D(polyline) => {
                 let positions = polyline.points.into_iter().map(FlatToRs::to_rs).collect();
 
-                Render::LineStrip {
+                Some(Render::LineStrip {
                     positions,
                     color: polyline.color.to_rs(),
-                }
+                })
             }
-            _ => unimplemented!(),
+            _ => None,
         }
     }
 }
@@ -136,7 +147,7 @@ impl FlatToRs<RenderMessage> for flat::RenderGroupT {
             self.id,
             self.render_messages
                 .into_iter()
-                .map(FlatToRs::to_rs)
+                .filter_map(FlatToRs::to_rs)
                 .collect(),
         )
     }
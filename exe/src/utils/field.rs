@@ -0,0 +1,185 @@
+use rlbot_sockets::flat;
+use rocketsim_rs::consts::{snowday::PUCK_HEIGHT, HOOPS_GOAL_SCORE_THRESHOLD_Z};
+
+const SOCCER_GOAL_Y: f32 = 5120.;
+const SOCCER_GOAL_WIDTH: f32 = 892.755 * 2.;
+const SOCCER_GOAL_HEIGHT: f32 = 642.775;
+
+/// The rim is an ellipse, squashed along y
+const HOOPS_RIM_RADIUS: f32 = 716.;
+const HOOPS_RIM_SCALE_Y: f32 = 0.9;
+const HOOPS_RIM_OFFSET_Y: f32 = 2770.;
+
+fn vec3(x: f32, y: f32, z: f32) -> flat::Vector3T {
+    flat::Vector3T { x, y, z }
+}
+
+/// `direction` points from the goal towards the field
+fn goal(
+    team: u32,
+    location: flat::Vector3T,
+    direction: flat::Vector3T,
+    width: f32,
+    height: f32,
+) -> flat::GoalInfoT {
+    let mut goal = flat::GoalInfoT::default();
+    goal.team_num = team;
+    goal.location = location;
+    goal.direction = direction;
+    goal.width = width;
+    goal.height = height;
+    goal
+}
+
+/// Blue's goal then orange's, with the location at the center of the opening
+pub fn goals(game_mode: flat::GameMode) -> Vec<flat::GoalInfoT> {
+    match game_mode {
+        flat::GameMode::Hoops => {
+            let rim_y = HOOPS_RIM_OFFSET_Y / HOOPS_RIM_SCALE_Y;
+            let width = HOOPS_RIM_RADIUS * 2.;
+            let depth = width / HOOPS_RIM_SCALE_Y;
+
+            // the ball drops into the hoops from above
+            vec![
+                goal(
+                    0,
+                    vec3(0., -rim_y, HOOPS_GOAL_SCORE_THRESHOLD_Z),
+                    vec3(0., 0., 1.),
+                    width,
+                    depth,
+                ),
+                goal(
+                    1,
+                    vec3(0., rim_y, HOOPS_GOAL_SCORE_THRESHOLD_Z),
+                    vec3(0., 0., 1.),
+                    width,
+                    depth,
+                ),
+            ]
+        }
+        // heatseeker, snowday and rumble are all played on the soccer field
+        _ => {
+            let z = SOCCER_GOAL_HEIGHT / 2.;
+
+            vec![
+                goal(
+                    0,
+                    vec3(0., -SOCCER_GOAL_Y, z),
+                    vec3(0., 1., 0.),
+                    SOCCER_GOAL_WIDTH,
+                    SOCCER_GOAL_HEIGHT,
+                ),
+                goal(
+                    1,
+                    vec3(0., SOCCER_GOAL_Y, z),
+                    vec3(0., -1., 0.),
+                    SOCCER_GOAL_WIDTH,
+                    SOCCER_GOAL_HEIGHT,
+                ),
+            ]
+        }
+    }
+}
+
+pub fn ball_shape(game_mode: flat::GameMode, ball_radius: f32) -> flat::CollisionShapeT {
+    if game_mode == flat::GameMode::Hockey {
+        let mut cylinder_shape = Box::<flat::CylinderShapeT>::default();
+        cylinder_shape.diameter = ball_radius * 2.;
+        cylinder_shape.height = PUCK_HEIGHT;
+        flat::CollisionShapeT::CylinderShape(cylinder_shape)
+    } else {
+        let mut sphere_shape = Box::<flat::SphereShapeT>::default();
+        sphere_shape.diameter = ball_radius * 2.;
+        flat::CollisionShapeT::SphereShape(sphere_shape)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_facing_each_other(goals: &[flat::GoalInfoT]) {
+        assert_eq!(goals.len(), 2);
+
+        let (blue, orange) = (&goals[0], &goals[1]);
+        assert_eq!(blue.team_num, 0);
+        assert_eq!(orange.team_num, 1);
+        assert!(blue.location.y < 0.);
+        assert_eq!(blue.location.y, -orange.location.y);
+        assert_eq!(blue.location.z, orange.location.z);
+        assert_eq!(blue.width, orange.width);
+        assert_eq!(blue.height, orange.height);
+    }
+
+    fn assert_sphere(shape: flat::CollisionShapeT, diameter: f32) {
+        let flat::CollisionShapeT::SphereShape(sphere) = shape else {
+            panic!("Expected a sphere");
+        };
+
+        assert_eq!(sphere.diameter, diameter);
+    }
+
+    #[test]
+    fn soccer() {
+        let goals = goals(flat::GameMode::Soccer);
+        assert_facing_each_other(&goals);
+
+        let blue = &goals[0];
+        assert_eq!(blue.location.y, -5120.);
+        assert_eq!(blue.location.z, SOCCER_GOAL_HEIGHT / 2.);
+        assert_eq!(blue.direction.y, 1.);
+        assert_eq!(goals[1].direction.y, -1.);
+        assert_eq!(blue.width, 1785.51);
+        assert_eq!(blue.height, 642.775);
+
+        assert_sphere(ball_shape(flat::GameMode::Soccer, 91.25), 182.5);
+    }
+
+    #[test]
+    fn hoops() {
+        let goals = goals(flat::GameMode::Hoops);
+        assert_facing_each_other(&goals);
+
+        let blue = &goals[0];
+        assert!(blue.location.y.abs() < SOCCER_GOAL_Y);
+        assert_eq!(blue.location.z, HOOPS_GOAL_SCORE_THRESHOLD_Z);
+        assert_eq!(blue.direction.z, 1.);
+        assert_eq!(blue.width, 1432.);
+        assert!(blue.height > blue.width);
+
+        assert_sphere(ball_shape(flat::GameMode::Hoops, 96.38), 192.76);
+    }
+
+    #[test]
+    fn heatseeker() {
+        let goals = goals(flat::GameMode::Heatseeker);
+        assert_facing_each_other(&goals);
+        assert_eq!(goals, super::goals(flat::GameMode::Soccer));
+
+        assert_sphere(ball_shape(flat::GameMode::Heatseeker, 91.25), 182.5);
+    }
+
+    #[test]
+    fn hockey() {
+        let goals = goals(flat::GameMode::Hockey);
+        assert_facing_each_other(&goals);
+        assert_eq!(goals, super::goals(flat::GameMode::Soccer));
+
+        let flat::CollisionShapeT::CylinderShape(puck) = ball_shape(flat::GameMode::Hockey, 114.25)
+        else {
+            panic!("Expected the puck to be a cylinder");
+        };
+
+        assert_eq!(puck.diameter, 228.5);
+        assert_eq!(puck.height, PUCK_HEIGHT);
+    }
+
+    #[test]
+    fn rumble() {
+        let goals = goals(flat::GameMode::Rumble);
+        assert_facing_each_other(&goals);
+        assert_eq!(goals, super::goals(flat::GameMode::Soccer));
+
+        assert_sphere(ball_shape(flat::GameMode::Rumble, 91.25), 182.5);
+    }
+}
@@ -0,0 +1,139 @@
+use clap::ValueEnum;
+use std::{collections::HashSet, time::Duration};
+use tokio::time::Instant;
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum TimeoutPolicy {
+    /// Step anyway, reusing the bot's last controls, and keep waiting for it on later ticks
+    #[default]
+    Continue,
+    /// Step anyway and stop waiting for the bot for the rest of the match
+    Drop,
+}
+
+/// Tracks which reserved players still owe an input for the current tick
+pub struct Lockstep {
+    timeout: Duration,
+    policy: TimeoutPolicy,
+    deadline: Instant,
+    ready: bool,
+    waiting_on: HashSet<u32, ahash::RandomState>,
+    dropped: HashSet<u32, ahash::RandomState>,
+}
+
+impl Lockstep {
+    pub fn new(timeout: Duration, policy: TimeoutPolicy) -> Self {
+        Self {
+            timeout,
+            policy,
+            deadline: Instant::now(),
+            ready: false,
+            waiting_on: HashSet::default(),
+            dropped: HashSet::default(),
+        }
+    }
+
+    /// Forget about any bots that were dropped in a previous match
+    pub fn reset(&mut self) {
+        self.ready = false;
+        self.waiting_on.clear();
+        self.dropped.clear();
+    }
+
+    /// Start waiting on an input from each of `players` for the tick that was just broadcast
+    pub fn start_tick(&mut self, players: impl Iterator<Item = u32>) {
+        self.ready = false;
+        self.waiting_on.clear();
+        self.waiting_on
+            .extend(players.filter(|index| !self.dropped.contains(index)));
+        self.deadline = Instant::now() + self.timeout;
+    }
+
+    /// If there's no one to wait on, the regular tick interval should be used instead
+    #[inline]
+    pub fn is_waiting(&self) -> bool {
+        !self.waiting_on.is_empty()
+    }
+
+    #[inline]
+    pub const fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    pub fn received_input(&mut self, player_index: u32) {
+        if self.waiting_on.remove(&player_index) && self.waiting_on.is_empty() {
+            self.ready = true;
+        }
+    }
+
+    /// Returns true once, after the last input needed to step the simulation has arrived
+    #[inline]
+    pub fn take_ready(&mut self) -> bool {
+        std::mem::take(&mut self.ready)
+    }
+
+    /// Stops waiting on players whose bots disconnected, whatever the timeout policy.
+    /// If they're reserved again, they're waited on like any other player.
+    pub fn release_players(&mut self, players: &[u32]) {
+        let was_waiting = self.is_waiting();
+
+        for index in players {
+            self.waiting_on.remove(index);
+            self.dropped.remove(index);
+        }
+
+        if was_waiting && !self.is_waiting() {
+            self.ready = true;
+        }
+    }
+
+    pub fn timed_out(&mut self) {
+        let mut late = self.waiting_on.drain().collect::<Vec<_>>();
+        late.sort_unstable();
+
+        match self.policy {
+            TimeoutPolicy::Continue => {
+                println!("Lockstep timed out waiting on players {late:?}");
+            }
+            TimeoutPolicy::Drop => {
+                println!("Lockstep timed out, no longer waiting on players {late:?}");
+                self.dropped.extend(late);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn released_players_are_not_waited_on() {
+        let mut lockstep = Lockstep::new(Duration::from_secs(1), TimeoutPolicy::Continue);
+
+        lockstep.start_tick([0, 1].into_iter());
+        lockstep.received_input(0);
+        assert!(lockstep.is_waiting());
+
+        // the last player being waited on disconnects, so the tick can go ahead
+        lockstep.release_players(&[1]);
+        assert!(!lockstep.is_waiting());
+        assert!(lockstep.take_ready());
+    }
+
+    #[test]
+    fn released_players_are_no_longer_dropped() {
+        let mut lockstep = Lockstep::new(Duration::from_secs(1), TimeoutPolicy::Drop);
+
+        lockstep.start_tick([0].into_iter());
+        lockstep.timed_out();
+        lockstep.start_tick([0].into_iter());
+        assert!(!lockstep.is_waiting());
+
+        // a bot that reconnects and reserves the player again is waited on
+        lockstep.release_players(&[0]);
+        lockstep.start_tick([0].into_iter());
+        assert!(lockstep.is_waiting());
+        assert!(!lockstep.take_ready());
+    }
+}
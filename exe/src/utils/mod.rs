@@ -1,5 +1,22 @@
+pub mod admin;
 pub mod agent_res;
+pub mod clock;
+pub mod console;
 pub mod conv;
+pub mod field;
 pub mod game;
+pub mod lockstep;
+pub mod multi_ball;
+pub mod packet_fields;
+pub mod packet_log;
 pub mod parse;
+pub mod playback;
+pub mod recording;
+pub mod rewind;
+pub mod rumble;
+pub mod snapshot;
+pub mod stats;
+pub mod telemetry;
+#[cfg(test)]
+pub mod test_utils;
 pub mod viser;
@@ -0,0 +1,102 @@
+use rocketsim_rs::{
+    cxx::UniquePtr,
+    sim::{Arena, BallState, CarConfig, Team},
+    GameState,
+};
+
+/// How far above the previous ball each extra ball is dropped at kickoff
+const KICKOFF_SPACING_Z: f32 = 250.;
+
+/// Simulates every ball after the first in an arena of its own,
+/// because RocketSim arenas only have one ball.
+///
+/// The cars are copied over from the main arena before every step,
+/// so extra balls react to cars but don't collide with each other or push cars around.
+#[derive(Default)]
+pub struct ExtraBalls {
+    arenas: Vec<UniquePtr<Arena>>,
+}
+
+impl ExtraBalls {
+    pub fn new(arenas: Vec<UniquePtr<Arena>>) -> Self {
+        Self { arenas }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.arenas.is_empty()
+    }
+
+    /// Cars have to be added in the same order as in the main arena so their ids line up
+    pub fn add_car(&mut self, team: Team, config: &CarConfig) {
+        for arena in &mut self.arenas {
+            let _ = arena.pin_mut().add_car(team, config);
+        }
+    }
+
+    /// Stacks the extra balls above the center spot so they don't start inside each other
+    pub fn reset_to_kickoff(&mut self, seed: Option<i32>) {
+        for (i, arena) in self.arenas.iter_mut().enumerate() {
+            arena.pin_mut().reset_to_random_kickoff(seed);
+
+            let mut ball = arena.pin_mut().get_ball();
+            ball.pos.z += KICKOFF_SPACING_Z * (i + 1) as f32;
+            arena.pin_mut().set_ball(ball);
+        }
+    }
+
+    /// Copies the cars from the main arena,
+    /// keeping each arena's own record of which car last hit its ball
+    pub fn sync_cars(&mut self, main: &GameState) {
+        for arena in &mut self.arenas {
+            let mut game_state = arena.pin_mut().get_game_state();
+
+            for car in &mut game_state.cars {
+                let Some(main_car) = main.cars.iter().find(|main_car| main_car.id == car.id) else {
+                    continue;
+                };
+
+                let ball_hit_info = car.state.ball_hit_info;
+                car.state = main_car.state;
+                car.state.ball_hit_info = ball_hit_info;
+            }
+
+            // the cars are the same as in the main arena, so this can't fail
+            let _ = arena.pin_mut().set_game_state(&game_state);
+        }
+    }
+
+    pub fn step(&mut self) {
+        for arena in &mut self.arenas {
+            arena.pin_mut().step(1);
+        }
+    }
+
+    pub fn game_states(&mut self) -> Vec<GameState> {
+        self.arenas
+            .iter_mut()
+            .map(|arena| arena.pin_mut().get_game_state())
+            .collect()
+    }
+
+    /// `index` 0 is the first extra ball, which is ball 1 in the packet
+    pub fn ball(&mut self, index: usize) -> Option<BallState> {
+        self.arenas
+            .get_mut(index)
+            .map(|arena| arena.pin_mut().get_ball())
+    }
+
+    /// Restores the whole arena, including which car last hit the ball
+    pub fn set_game_state(&mut self, index: usize, game_state: &GameState) {
+        if let Some(arena) = self.arenas.get_mut(index) {
+            // the state came from this arena, so the cars are the same
+            let _ = arena.pin_mut().set_game_state(game_state);
+        }
+    }
+
+    pub fn set_ball(&mut self, index: usize, ball: BallState) {
+        if let Some(arena) = self.arenas.get_mut(index) {
+            arena.pin_mut().set_ball(ball);
+        }
+    }
+}
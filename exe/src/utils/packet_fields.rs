@@ -0,0 +1,224 @@
+use rlbot_sockets::flat;
+use rocketsim_rs::{
+    consts::DOUBLEJUMP_MAX_DELAY,
+    sim::{BoostPadState, CarState, MutatorConfig},
+};
+
+/// Dodging takes priority over jumping, like in Rocket League.
+/// A jump is reported from its first tick, even while the wheels are still touching the ground.
+/// RocketSim's double jump is a single impulse,
+/// so it's reported for as long as the jump button that triggered it is held.
+pub fn air_state(state: &CarState) -> flat::AirState {
+    if state.is_on_ground && !state.is_jumping {
+        flat::AirState::OnGround
+    } else if state.is_flipping {
+        flat::AirState::Dodging
+    } else if state.is_jumping {
+        flat::AirState::Jumping
+    } else if state.has_double_jumped && state.last_controls.jump {
+        flat::AirState::DoubleJumping
+    } else {
+        flat::AirState::InAir
+    }
+}
+
+/// The time left to dodge or double jump, or -1 if the car can't anymore
+pub fn dodge_timeout(state: &CarState) -> f32 {
+    if (state.is_on_ground && !state.is_jumping) || state.has_double_jumped || state.has_flipped {
+        return -1.;
+    }
+
+    let remaining = DOUBLEJUMP_MAX_DELAY - state.air_time_since_jump;
+    if remaining > 0. {
+        remaining
+    } else {
+        -1.
+    }
+}
+
+/// -1 if the car is alive, otherwise the time until it respawns
+pub fn demolished_timeout(state: &CarState) -> f32 {
+    if state.is_demoed {
+        state.demo_respawn_timer
+    } else {
+        -1.
+    }
+}
+
+/// The time since the pad was picked up, 0 while it's active
+pub fn boost_pad_timer(is_big: bool, state: &BoostPadState, mutators: &MutatorConfig) -> f32 {
+    if state.is_active {
+        return 0.;
+    }
+
+    let cooldown = if is_big {
+        mutators.boost_pad_cooldown_big
+    } else {
+        mutators.boost_pad_cooldown_small
+    };
+
+    (cooldown - state.cooldown).max(0.)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{
+        game::GAME_DT,
+        test_utils::{add_car, arena_with_car},
+    };
+    use rocketsim_rs::{
+        cxx::UniquePtr,
+        math::Vec3,
+        sim::{Arena, CarControls, Team},
+    };
+
+    fn step(
+        arena: &mut UniquePtr<Arena>,
+        car_id: u32,
+        controls: CarControls,
+        ticks: u32,
+    ) -> CarState {
+        arena.pin_mut().set_car_controls(car_id, controls).unwrap();
+        arena.pin_mut().step(ticks);
+        arena.pin_mut().get_car(car_id)
+    }
+
+    fn jump() -> CarControls {
+        CarControls {
+            jump: true,
+            ..Default::default()
+        }
+    }
+
+    /// Holds jump for a few ticks, then lets go until the first jump is over
+    fn after_first_jump(arena: &mut UniquePtr<Arena>, car_id: u32) -> CarState {
+        step(arena, car_id, jump(), 5);
+        let state = step(arena, car_id, CarControls::default(), 30);
+        assert!(!state.is_on_ground && !state.is_jumping);
+
+        state
+    }
+
+    #[test]
+    fn on_ground() {
+        let (mut arena, car_id) = arena_with_car();
+        let state = arena.pin_mut().get_car(car_id);
+
+        assert_eq!(air_state(&state), flat::AirState::OnGround);
+        assert_eq!(dodge_timeout(&state), -1.);
+        assert_eq!(demolished_timeout(&state), -1.);
+    }
+
+    #[test]
+    fn first_jump() {
+        let (mut arena, car_id) = arena_with_car();
+
+        let state = step(&mut arena, car_id, jump(), 2);
+        assert_eq!(air_state(&state), flat::AirState::Jumping);
+        assert!(dodge_timeout(&state) > 0.);
+
+        let state = after_first_jump(&mut arena, car_id);
+        assert_eq!(air_state(&state), flat::AirState::InAir);
+
+        // the window closes as time passes after the jump
+        let timeout = dodge_timeout(&state);
+        assert!(timeout > 0. && timeout < DOUBLEJUMP_MAX_DELAY);
+
+        let later = step(&mut arena, car_id, CarControls::default(), 12);
+        assert!((timeout - dodge_timeout(&later) - 12. * GAME_DT).abs() < 1e-3);
+    }
+
+    #[test]
+    fn double_jump() {
+        let (mut arena, car_id) = arena_with_car();
+        after_first_jump(&mut arena, car_id);
+
+        let state = step(&mut arena, car_id, jump(), 1);
+        assert!(state.has_double_jumped);
+        assert_eq!(air_state(&state), flat::AirState::DoubleJumping);
+        assert_eq!(dodge_timeout(&state), -1.);
+
+        let state = step(&mut arena, car_id, CarControls::default(), 1);
+        assert_eq!(air_state(&state), flat::AirState::InAir);
+    }
+
+    #[test]
+    fn dodge() {
+        let (mut arena, car_id) = arena_with_car();
+        after_first_jump(&mut arena, car_id);
+
+        let controls = CarControls {
+            jump: true,
+            pitch: -1.,
+            ..Default::default()
+        };
+        let state = step(&mut arena, car_id, controls, 1);
+        assert!(state.has_flipped);
+        assert_eq!(air_state(&state), flat::AirState::Dodging);
+        assert_eq!(dodge_timeout(&state), -1.);
+    }
+
+    #[test]
+    fn pad_pickup() {
+        let (mut arena, car_id) = arena_with_car();
+        let mutators = arena.get_mutator_config();
+
+        let (pad_index, pad) = arena
+            .iter_pad_config()
+            .enumerate()
+            .find(|(_, pad)| pad.is_big)
+            .unwrap();
+        assert_eq!(
+            boost_pad_timer(true, &arena.get_pad_state(pad_index), &mutators),
+            0.
+        );
+
+        let mut state = arena.pin_mut().get_car(car_id);
+        state.pos = Vec3::new(pad.position.x, pad.position.y, 17.);
+        state.boost = 0.;
+        arena.pin_mut().set_car(car_id, state).unwrap();
+
+        arena.pin_mut().step(1);
+        let pad_state = arena.get_pad_state(pad_index);
+        assert!(!pad_state.is_active);
+        let picked_up = boost_pad_timer(true, &pad_state, &mutators);
+        assert!(picked_up < 2. * GAME_DT);
+
+        arena.pin_mut().step(120);
+        let timer = boost_pad_timer(true, &arena.get_pad_state(pad_index), &mutators);
+        assert!((timer - picked_up - 1.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn demolition() {
+        let (mut arena, car_id) = arena_with_car();
+        let victim = add_car(
+            &mut arena,
+            Team::Orange,
+            Vec3::new(0., -1500., 17.),
+            Vec3::new(0., 0., 0.),
+        );
+
+        // drive into the victim at full speed
+        let mut state = arena.pin_mut().get_car(car_id);
+        state.vel = Vec3::new(0., 2300., 0.);
+        state.is_supersonic = true;
+        arena.pin_mut().set_car(car_id, state).unwrap();
+
+        let controls = CarControls {
+            throttle: 1.,
+            boost: true,
+            ..Default::default()
+        };
+        arena.pin_mut().set_car_controls(car_id, controls).unwrap();
+        arena.pin_mut().step(30);
+
+        let state = arena.pin_mut().get_car(victim);
+        assert!(state.is_demoed);
+
+        let respawn_delay = arena.get_mutator_config().respawn_delay;
+        let timeout = demolished_timeout(&state);
+        assert!(timeout > 0. && timeout < respawn_delay);
+    }
+}
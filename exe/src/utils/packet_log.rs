@@ -0,0 +1,121 @@
+use crate::messages::FromGame;
+use rlbot_sockets::SocketDataType;
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Result as IoResult, Write},
+    path::Path,
+};
+
+const MAGIC: &[u8; 4] = b"RLPL";
+const VERSION: u16 = 1;
+
+/// The socket data type and payload for the messages that end up in a packet log
+fn logged_payload(msg: &FromGame) -> Option<(SocketDataType, &[u8])> {
+    match msg {
+        FromGame::GameTickPacket(bytes) => Some((SocketDataType::GamePacket, bytes)),
+        FromGame::BallPrediction(bytes) => Some((SocketDataType::BallPrediction, bytes)),
+        FromGame::MatchComm(bytes) => Some((SocketDataType::MatchComm, bytes)),
+        FromGame::MatchSettings(bytes) => Some((SocketDataType::MatchConfig, bytes)),
+        FromGame::FieldInfo(bytes) => Some((SocketDataType::FieldInfo, bytes)),
+        FromGame::StopCommand(_) | FromGame::Demolition(_) => None,
+    }
+}
+
+/// Writes the serialized messages that the game thread broadcasts.
+///
+/// File layout: `RLPL`, a little-endian u16 version,
+/// then entries of a little-endian u64 frame followed by a regular socket frame.
+pub struct PacketLogWriter {
+    writer: BufWriter<File>,
+    frame: u64,
+}
+
+impl PacketLogWriter {
+    pub fn create(path: &Path) -> IoResult<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+
+        Ok(Self { writer, frame: 0 })
+    }
+
+    pub fn write(&mut self, msg: &FromGame) -> IoResult<()> {
+        let Some((data_type, payload)) = logged_payload(msg) else {
+            return Ok(());
+        };
+
+        let size = u16::try_from(payload.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Payload is too large"))?;
+
+        self.writer.write_all(&self.frame.to_le_bytes())?;
+        self.writer.write_all(&(data_type as u16).to_be_bytes())?;
+        self.writer.write_all(&size.to_be_bytes())?;
+        self.writer.write_all(payload)
+    }
+
+    /// Flushes the frame's entries so the log is complete even if the server is killed
+    pub fn end_frame(&mut self) -> IoResult<()> {
+        self.frame += 1;
+        self.writer.flush()
+    }
+}
+
+pub struct LogEntry {
+    pub frame: u64,
+    pub data_type: SocketDataType,
+    pub payload: Box<[u8]>,
+}
+
+/// Reads back the entries written by a [`PacketLogWriter`]
+pub struct PacketLogReader {
+    reader: BufReader<File>,
+}
+
+impl PacketLogReader {
+    pub fn open(path: &Path) -> IoResult<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+
+        let mut header = [0; 6];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Not a packet log file",
+            ));
+        }
+
+        let version = u16::from_le_bytes([header[4], header[5]]);
+        if version != VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unsupported packet log version {version}"),
+            ));
+        }
+
+        Ok(Self { reader })
+    }
+
+    /// Returns `None` at the end of the file
+    pub fn next_entry(&mut self) -> IoResult<Option<LogEntry>> {
+        let mut header = [0; 12];
+        match self.reader.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+
+        let frame = u64::from_le_bytes(header[..8].try_into().unwrap());
+        let data_type = SocketDataType::try_from(u16::from_be_bytes([header[8], header[9]]))
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let size = usize::from(u16::from_be_bytes([header[10], header[11]]));
+
+        let mut payload = vec![0; size].into_boxed_slice();
+        self.reader.read_exact(&mut payload)?;
+
+        Ok(Some(LogEntry {
+            frame,
+            data_type,
+            payload,
+        }))
+    }
+}
@@ -1,14 +1,22 @@
+use crate::messages::ExtraMatchSettings;
 use rlbot_sockets::flat;
 use std::{
     collections::HashMap,
     hash::{DefaultHasher, Hash, Hasher},
-    io::Result as IoResult,
+    io::{Error as IoError, ErrorKind, Result as IoResult},
     path::Path,
 };
 use tokio::fs;
 use toml::{map::Map, Value};
 
-pub async fn file_to_match_settings(path: String) -> IoResult<flat::MatchConfigurationT> {
+/// Every extra ball is simulated in an arena of its own, so there can't be too many of them
+const MAX_BALL_COUNT: u32 = 16;
+
+/// Reads a match config file, returning the match settings
+/// and the options that only this server understands
+pub async fn file_to_match_settings(
+    path: String,
+) -> IoResult<(flat::MatchConfigurationT, ExtraMatchSettings)> {
     let empty_map = Map::new();
     let empty_vec = Vec::new();
 
@@ -16,7 +24,7 @@ pub async fn file_to_match_settings(path: String) -> IoResult<flat::MatchConfigu
     let file_str = fs::read_to_string(path).await?;
     let toml = file_str
         .parse::<toml::Table>()
-        .unwrap_or_else(|_| empty_map.clone());
+        .map_err(|e| invalid_data(format!("{} isn't valid TOML: {e}", path.display())))?;
 
     let mut settings = flat::MatchConfigurationT::default();
 
@@ -30,17 +38,52 @@ pub async fn file_to_match_settings(path: String) -> IoResult<flat::MatchConfigu
         .and_then(Value::as_bool)
         .unwrap_or(true);
 
-    let match_header = toml["match"].as_table().unwrap_or(&empty_map);
+    let match_header = toml
+        .get("match")
+        .and_then(Value::as_table)
+        .ok_or_else(|| invalid_data("the match config has no [match] table"))?;
 
-    settings.game_mode = match_header["game_mode"]
-        .as_str()
-        .unwrap()
+    settings.game_mode = match_header
+        .get("game_mode")
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid_data("the match config has no game_mode"))?
         .parse()
         .unwrap_or_default();
     settings.instant_start = match_header
         .get("start_without_countdown")
         .and_then(toml::Value::as_bool)
         .unwrap_or_default();
+    settings.skip_replays = match_header
+        .get("skip_replays")
+        .and_then(toml::Value::as_bool)
+        .unwrap_or_default();
+    let seed = match_header
+        .get("seed")
+        .and_then(Value::as_integer)
+        .and_then(|seed| u32::try_from(seed).ok());
+    let snapshot = match_header
+        .get("snapshot")
+        .and_then(Value::as_str)
+        .map(|snapshot| path.parent().unwrap().join(snapshot));
+    let ball_count = match_header
+        .get("ball_count")
+        .and_then(Value::as_integer)
+        .and_then(|ball_count| u32::try_from(ball_count).ok())
+        .filter(|&ball_count| ball_count > 0)
+        .map(|ball_count| ball_count.min(MAX_BALL_COUNT));
+
+    let mutators_header = toml
+        .get("mutators")
+        .and_then(Value::as_table)
+        .unwrap_or(&empty_map);
+
+    let mut mutators = flat::MutatorSettingsT::default();
+    mutators.match_length = mutators_header
+        .get("match_length")
+        .and_then(Value::as_str)
+        .and_then(|s| s.parse().ok())
+        .unwrap_or_default();
+    settings.mutators = Some(Box::new(mutators));
 
     let cars_header = toml
         .get("cars")
@@ -57,7 +100,10 @@ pub async fn file_to_match_settings(path: String) -> IoResult<flat::MatchConfigu
 
         player.variety = flat::PlayerClassT::CustomBot(Box::default());
 
-        let player_team = car["team"].as_integer().unwrap_or_default();
+        let player_team = car
+            .get("team")
+            .and_then(Value::as_integer)
+            .unwrap_or_default();
         player.team = player_team as u32;
 
         let Some(relative_config_path) = car.get("config").and_then(|c| c.as_str()) else {
@@ -75,9 +121,13 @@ pub async fn file_to_match_settings(path: String) -> IoResult<flat::MatchConfigu
             .parse::<toml::Table>()
             .unwrap_or_else(|_| empty_map.clone());
 
-        let settings_header = config_toml["settings"].as_table().unwrap_or(&empty_map);
-        let name = settings_header["name"]
-            .as_str()
+        let settings_header = config_toml
+            .get("settings")
+            .and_then(Value::as_table)
+            .unwrap_or(&empty_map);
+        let name = settings_header
+            .get("name")
+            .and_then(Value::as_str)
             .unwrap_or_default()
             .to_string();
 
@@ -107,25 +157,37 @@ pub async fn file_to_match_settings(path: String) -> IoResult<flat::MatchConfigu
             .to_string_lossy()
             .to_string();
 
-        player.run_command = if cfg!(windows) {
-            &settings_header["run_command"]
+        let run_command_linux = if cfg!(windows) {
+            None
         } else {
-            settings_header
-                .get("run_command_linux")
-                .unwrap_or_else(|| &settings_header["run_command"])
-        }
-        .as_str()
-        .unwrap_or_default()
-        .parse()
-        .unwrap_or_default();
+            settings_header.get("run_command_linux")
+        };
+        player.run_command = run_command_linux
+            .or_else(|| settings_header.get("run_command"))
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .parse()
+            .unwrap_or_default();
 
-        player.agent_id = settings_header["agent_id"]
-            .as_str()
+        player.agent_id = settings_header
+            .get("agent_id")
+            .and_then(Value::as_str)
             .unwrap_or_default()
             .to_string();
 
         settings.player_configurations.push(player);
     }
 
-    Ok(settings)
+    Ok((
+        settings,
+        ExtraMatchSettings {
+            seed,
+            snapshot,
+            ball_count,
+        },
+    ))
+}
+
+fn invalid_data(msg: impl Into<String>) -> IoError {
+    IoError::new(ErrorKind::InvalidData, msg.into())
 }
@@ -0,0 +1,260 @@
+use crate::{
+    messages::{Envelope, FromGame, ToGame},
+    utils::{
+        agent_res::AgentReservation,
+        conv::FlatToRs,
+        game::GAME_DT,
+        packet_log::{LogEntry, PacketLogReader},
+        viser,
+    },
+    RLVISER_PATH, RLVISER_PORT, ROCKETSIM_PORT,
+};
+use async_timer::interval;
+use rlbot_sockets::{
+    flat,
+    flatbuffers::{root, FlatBufferBuilder},
+    SocketDataType,
+};
+use rocketsim_rs::{
+    cxx::UniquePtr,
+    sim::{Arena, CarConfig, Team},
+    GameState,
+};
+use std::{io::Result as IoResult, path::Path, time::Duration};
+use tokio::sync::{broadcast, mpsc};
+
+/// Rebuilds a `GameState` from logged packets so RLViser can show them, without ever stepping
+struct ViewerState {
+    arena: UniquePtr<Arena>,
+    car_ids: Vec<u32>,
+}
+
+impl ViewerState {
+    fn new(game_mode: flat::GameMode) -> Self {
+        Self {
+            arena: match game_mode {
+                flat::GameMode::Hoops => Arena::default_hoops(),
+                flat::GameMode::Heatseeker => Arena::default_heatseeker(),
+                flat::GameMode::Hockey => Arena::default_snowday(),
+                _ => Arena::default_standard(),
+            },
+            car_ids: Vec::new(),
+        }
+    }
+
+    fn game_state(&mut self, packet: &flat::GamePacketT) -> GameState {
+        for player in &packet.players[self.car_ids.len().min(packet.players.len())..] {
+            let team = if player.team == 0 {
+                Team::Blue
+            } else {
+                Team::Orange
+            };
+
+            let car_id = self.arena.pin_mut().add_car(team, CarConfig::octane());
+            self.car_ids.push(car_id);
+        }
+
+        let mut game_state = self.arena.pin_mut().get_game_state();
+        game_state.tick_count = u64::from(packet.match_info.frame_num);
+
+        if let Some(ball) = packet.balls.first() {
+            game_state.ball.pos = ball.physics.location.to_rs();
+            game_state.ball.vel = ball.physics.velocity.to_rs();
+            game_state.ball.ang_vel = ball.physics.angular_velocity.to_rs();
+            game_state.ball.rot_mat = ball.physics.rotation.to_rs();
+        }
+
+        for (player, car_id) in packet.players.iter().zip(&self.car_ids) {
+            let Some(car) = game_state.cars.iter_mut().find(|car| car.id == *car_id) else {
+                continue;
+            };
+
+            car.state.pos = player.physics.location.to_rs();
+            car.state.vel = player.physics.velocity.to_rs();
+            car.state.ang_vel = player.physics.angular_velocity.to_rs();
+            car.state.rot_mat = player.physics.rotation.to_rs();
+            car.state.boost = player.boost as f32;
+            car.state.is_demoed = player.demolished_timeout >= 0.;
+        }
+
+        game_state
+    }
+}
+
+struct Playback<'a> {
+    reader: PacketLogReader,
+    next_entry: Option<LogEntry>,
+    frame: u64,
+    tx: &'a broadcast::Sender<FromGame>,
+    flat_builder: FlatBufferBuilder<'static>,
+    match_settings: Option<Box<[u8]>>,
+    field_info: Option<Box<[u8]>>,
+    agent_reservation: AgentReservation,
+    viewer: ViewerState,
+}
+
+impl Playback<'_> {
+    /// Broadcasts every entry logged during the current frame,
+    /// returning the last game packet or `None` once the log is over
+    fn next_frame(&mut self) -> IoResult<Option<Option<flat::GamePacketT>>> {
+        if self.next_entry.is_none() {
+            self.next_entry = self.reader.next_entry()?;
+
+            if self.next_entry.is_none() {
+                return Ok(None);
+            }
+        }
+
+        let mut packet = None;
+
+        while let Some(entry) = self.next_entry.take() {
+            if entry.frame > self.frame {
+                self.next_entry = Some(entry);
+                break;
+            }
+
+            if let Some(new_packet) = self.play_entry(entry) {
+                packet = Some(new_packet);
+            }
+
+            self.next_entry = self.reader.next_entry()?;
+        }
+
+        self.frame += 1;
+        Ok(Some(packet))
+    }
+
+    fn play_entry(&mut self, entry: LogEntry) -> Option<flat::GamePacketT> {
+        let mut packet = None;
+
+        let msg = match entry.data_type {
+            SocketDataType::GamePacket => {
+                packet = root::<flat::GamePacket>(&entry.payload)
+                    .ok()
+                    .map(|packet| packet.unpack());
+                FromGame::GameTickPacket(entry.payload)
+            }
+            SocketDataType::BallPrediction => FromGame::BallPrediction(entry.payload),
+            SocketDataType::MatchComm => FromGame::MatchComm(entry.payload),
+            SocketDataType::MatchConfig => {
+                if let Ok(match_settings) = root::<flat::MatchConfiguration>(&entry.payload) {
+                    let match_settings = match_settings.unpack();
+                    self.agent_reservation.set_players(&match_settings);
+                    self.viewer = ViewerState::new(match_settings.game_mode);
+                }
+
+                self.match_settings = Some(entry.payload.clone());
+                FromGame::MatchSettings(entry.payload)
+            }
+            SocketDataType::FieldInfo => {
+                self.field_info = Some(entry.payload.clone());
+                FromGame::FieldInfo(entry.payload)
+            }
+            data_type => {
+                println!("Skipping unexpected {data_type:?} in the packet log");
+                return None;
+            }
+        };
+
+        let _ = self.tx.send(msg);
+        packet
+    }
+
+    /// Answers the requests that clients need to get going,
+    /// anything that would change the game is ignored
+    fn handle_message_from_client(&mut self, msg: ToGame) -> bool {
+        match msg {
+            ToGame::FieldInfoRequest(sender) => {
+                if let Some(field_info) = &self.field_info {
+                    let _ = sender.send(field_info.clone());
+                }
+            }
+            ToGame::MatchSettingsRequest(sender) => {
+                if let Some(match_settings) = &self.match_settings {
+                    let _ = sender.send(match_settings.clone());
+                }
+            }
+            ToGame::ControllableTeamInfoRequest(client, agent_id, sender) => {
+                let msg = self
+                    .agent_reservation
+                    .reserve_player(client, &agent_id)
+                    .map(|team_controllable_info| {
+                        self.flat_builder.reset();
+                        let offset = team_controllable_info.pack(&mut self.flat_builder);
+                        self.flat_builder.finish(offset, None);
+                        self.flat_builder.finished_data().into()
+                    });
+
+                let _ = sender.send(msg);
+            }
+            ToGame::StopCommand(info) => {
+                let _ = self.tx.send(FromGame::StopCommand(info.shutdown_server));
+                return !info.shutdown_server;
+            }
+            _ => {}
+        }
+
+        true
+    }
+}
+
+/// Serves a packet log made with `--log-packets` over the RLBot protocol at the original tick rate
+#[tokio::main(worker_threads = 2)]
+pub async fn run_playback(
+    path: &Path,
+    use_rlviser: bool,
+    tx: &broadcast::Sender<FromGame>,
+    mut rx: mpsc::Receiver<Envelope>,
+) -> IoResult<()> {
+    let mut playback = Playback {
+        reader: PacketLogReader::open(path)?,
+        next_entry: None,
+        frame: 0,
+        tx,
+        flat_builder: FlatBufferBuilder::with_capacity(1024),
+        match_settings: None,
+        field_info: None,
+        agent_reservation: AgentReservation::default(),
+        viewer: ViewerState::new(flat::GameMode::Soccer),
+    };
+
+    let rlviser = if use_rlviser {
+        Some(
+            viser::ExternalManager::new(Path::new(RLVISER_PATH), RLVISER_PORT, ROCKETSIM_PORT)
+                .await?,
+        )
+    } else {
+        None
+    };
+
+    let mut timer = interval(Duration::from_secs_f32(GAME_DT));
+
+    loop {
+        tokio::select! {
+            biased;
+            () = timer.wait() => {
+                let Some(packet) = playback.next_frame()? else {
+                    println!("Reached the end of the packet log");
+                    let _ = tx.send(FromGame::StopCommand(true));
+                    break;
+                };
+
+                if let (Some(rlviser), Some(packet)) = (&rlviser, packet) {
+                    rlviser.send_game_state(&playback.viewer.game_state(&packet)).await?;
+                }
+            }
+            Some(envelope) = rx.recv() => {
+                if !playback.handle_message_from_client(envelope.msg) {
+                    break;
+                }
+            }
+            else => break,
+        }
+    }
+
+    if let Some(rlviser) = &rlviser {
+        rlviser.close().await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,286 @@
+use rlbot_sockets::{
+    flat,
+    flatbuffers::{root, FlatBufferBuilder},
+};
+use rocketsim_rs::{bytes::ToBytes, GameState};
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Result as IoResult, Write},
+    path::Path,
+};
+
+const MAGIC: &[u8; 4] = b"RLSR";
+const VERSION: u16 = 3;
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug)]
+enum RecordKind {
+    MatchConfig,
+    InitialState,
+    PlayerInput,
+    DesiredGameState,
+    ViewerGameState,
+    Paused,
+    Checksum,
+    Snapshot,
+    Rewind,
+}
+
+impl TryFrom<u8> for RecordKind {
+    type Error = io::Error;
+
+    fn try_from(val: u8) -> Result<Self, Self::Error> {
+        Ok(match val {
+            0 => Self::MatchConfig,
+            1 => Self::InitialState,
+            2 => Self::PlayerInput,
+            3 => Self::DesiredGameState,
+            4 => Self::ViewerGameState,
+            5 => Self::Paused,
+            6 => Self::Checksum,
+            7 => Self::Snapshot,
+            8 => Self::Rewind,
+            _ => return Err(invalid_data(format!("Unknown record type {val}"))),
+        })
+    }
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// FNV-1a, so checksums stay the same across builds and platforms
+pub fn checksum(game_state: &GameState) -> u64 {
+    game_state
+        .to_bytes()
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+/// Everything that was fed into the simulation during a single frame, in the order it was applied
+pub enum Record {
+    MatchConfig {
+        seed: u32,
+        ball_count: u32,
+        settings: flat::MatchConfigurationT,
+    },
+    InitialState(Vec<u8>),
+    PlayerInput(flat::PlayerInputT),
+    DesiredGameState(flat::DesiredGameStateT),
+    ViewerGameState(Vec<u8>),
+    Paused(bool),
+    Checksum(u64),
+    /// The match was started from a snapshot instead of a kickoff
+    Snapshot(Vec<u8>),
+    /// The tick that the match was rewound to
+    Rewind(u64),
+}
+
+/// Writes every input to the simulation, plus a checksum of the resulting state after each frame.
+///
+/// File layout: `RLSR`, a u16 version, the u32 seconds kept for rewinding,
+/// then records of `u8 kind + u64 frame + u32 length + payload`, all little-endian.
+pub struct Recorder {
+    writer: BufWriter<File>,
+    builder: FlatBufferBuilder<'static>,
+    frame: u64,
+}
+
+impl Recorder {
+    /// `rewind_seconds` has to match when replaying, for rewinds to land on the same frames
+    pub fn create(path: &Path, rewind_seconds: u32) -> IoResult<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&rewind_seconds.to_le_bytes())?;
+
+        Ok(Self {
+            writer,
+            builder: FlatBufferBuilder::with_capacity(1024),
+            frame: 0,
+        })
+    }
+
+    fn write_record(&mut self, kind: RecordKind, payload: &[u8]) -> IoResult<()> {
+        self.writer.write_all(&[kind as u8])?;
+        self.writer.write_all(&self.frame.to_le_bytes())?;
+        self.writer
+            .write_all(&(payload.len() as u32).to_le_bytes())?;
+        self.writer.write_all(payload)
+    }
+
+    pub fn match_config(
+        &mut self,
+        seed: u32,
+        ball_count: u32,
+        settings: &flat::MatchConfigurationT,
+        initial_state: &GameState,
+    ) -> IoResult<()> {
+        self.builder.reset();
+        let offset = settings.pack(&mut self.builder);
+        self.builder.finish(offset, None);
+
+        let mut payload = seed.to_le_bytes().to_vec();
+        payload.extend_from_slice(&ball_count.to_le_bytes());
+        payload.extend_from_slice(self.builder.finished_data());
+        self.write_record(RecordKind::MatchConfig, &payload)?;
+
+        self.write_record(RecordKind::InitialState, &initial_state.to_bytes())
+    }
+
+    pub fn player_input(&mut self, input: &flat::PlayerInputT) -> IoResult<()> {
+        self.builder.reset();
+        let offset = input.pack(&mut self.builder);
+        self.builder.finish(offset, None);
+
+        let payload = self.builder.finished_data().to_vec();
+        self.write_record(RecordKind::PlayerInput, &payload)
+    }
+
+    pub fn desired_game_state(&mut self, desired_state: &flat::DesiredGameStateT) -> IoResult<()> {
+        self.builder.reset();
+        let offset = desired_state.pack(&mut self.builder);
+        self.builder.finish(offset, None);
+
+        let payload = self.builder.finished_data().to_vec();
+        self.write_record(RecordKind::DesiredGameState, &payload)
+    }
+
+    pub fn viewer_game_state(&mut self, game_state: &GameState) -> IoResult<()> {
+        self.write_record(RecordKind::ViewerGameState, &game_state.to_bytes())
+    }
+
+    pub fn snapshot(&mut self, game_state: &GameState) -> IoResult<()> {
+        self.write_record(RecordKind::Snapshot, &game_state.to_bytes())
+    }
+
+    pub fn rewind(&mut self, tick: u64) -> IoResult<()> {
+        self.write_record(RecordKind::Rewind, &tick.to_le_bytes())
+    }
+
+    pub fn paused(&mut self, paused: bool) -> IoResult<()> {
+        self.write_record(RecordKind::Paused, &[u8::from(paused)])
+    }
+
+    /// Ends the current frame
+    pub fn end_frame(&mut self, game_state: &GameState) -> IoResult<()> {
+        self.write_record(RecordKind::Checksum, &checksum(game_state).to_le_bytes())?;
+        self.frame += 1;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> IoResult<()> {
+        self.writer.flush()
+    }
+}
+
+/// Reads back the records written by a [`Recorder`]
+pub struct RecordingReader {
+    reader: BufReader<File>,
+    payload: Vec<u8>,
+    rewind_seconds: u32,
+}
+
+impl RecordingReader {
+    pub fn open(path: &Path) -> IoResult<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+
+        let mut header = [0; 6];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(invalid_data("Not a recording file".to_string()));
+        }
+
+        let version = u16::from_le_bytes([header[4], header[5]]);
+        if version != VERSION {
+            return Err(invalid_data(format!(
+                "Unsupported recording version {version}"
+            )));
+        }
+
+        let mut rewind_seconds = [0; 4];
+        reader.read_exact(&mut rewind_seconds)?;
+
+        Ok(Self {
+            reader,
+            payload: Vec::new(),
+            rewind_seconds: u32::from_le_bytes(rewind_seconds),
+        })
+    }
+
+    /// How many seconds the recorded match kept for rewinding
+    #[inline]
+    pub const fn rewind_seconds(&self) -> u32 {
+        self.rewind_seconds
+    }
+
+    /// Returns the frame the record belongs to along with the record,
+    /// or `None` at the end of the file
+    pub fn next_record(&mut self) -> IoResult<Option<(u64, Record)>> {
+        let mut header = [0; 13];
+        match self.reader.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+
+        let kind = RecordKind::try_from(header[0])?;
+        let frame = u64::from_le_bytes(header[1..9].try_into().unwrap());
+        let len = u32::from_le_bytes(header[9..13].try_into().unwrap()) as usize;
+
+        self.payload.resize(len, 0);
+        self.reader.read_exact(&mut self.payload)?;
+
+        let invalid = |e| invalid_data(format!("Invalid {kind:?} record on frame {frame}: {e}"));
+
+        let record = match kind {
+            RecordKind::MatchConfig => {
+                if len < 8 {
+                    return Err(invalid_data(format!("Truncated {kind:?} record")));
+                }
+
+                let seed = u32::from_le_bytes(self.payload[..4].try_into().unwrap());
+                let ball_count = u32::from_le_bytes(self.payload[4..8].try_into().unwrap());
+                let settings = root::<flat::MatchConfiguration>(&self.payload[8..])
+                    .map_err(invalid)?
+                    .unpack();
+
+                Record::MatchConfig {
+                    seed,
+                    ball_count,
+                    settings,
+                }
+            }
+            RecordKind::InitialState => Record::InitialState(self.payload.clone()),
+            RecordKind::PlayerInput => Record::PlayerInput(
+                root::<flat::PlayerInput>(&self.payload)
+                    .map_err(invalid)?
+                    .unpack(),
+            ),
+            RecordKind::DesiredGameState => Record::DesiredGameState(
+                root::<flat::DesiredGameState>(&self.payload)
+                    .map_err(invalid)?
+                    .unpack(),
+            ),
+            RecordKind::ViewerGameState => Record::ViewerGameState(self.payload.clone()),
+            RecordKind::Snapshot => Record::Snapshot(self.payload.clone()),
+            RecordKind::Rewind => Record::Rewind(u64::from_le_bytes(
+                self.payload
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| invalid_data(format!("Truncated {kind:?} record")))?,
+            )),
+            RecordKind::Paused => Record::Paused(self.payload.first().is_some_and(|&b| b != 0)),
+            RecordKind::Checksum => Record::Checksum(u64::from_le_bytes(
+                self.payload
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| invalid_data(format!("Truncated {kind:?} record")))?,
+            )),
+        };
+
+        Ok(Some((frame, record)))
+    }
+}
@@ -0,0 +1,53 @@
+use std::collections::VecDeque;
+
+/// Keeps the frames of the most recent ticks, at most one per tick
+pub struct RewindBuffer<T> {
+    frames: VecDeque<(u64, T)>,
+    capacity: usize,
+}
+
+impl<T: Clone> RewindBuffer<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            frames: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    /// Replaces any frames that aren't older than `tick`,
+    /// so frames taken while the arena isn't stepping don't pile up
+    pub fn push(&mut self, tick: u64, frame: T) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        while self.frames.back().is_some_and(|(last, _)| *last >= tick) {
+            self.frames.pop_back();
+        }
+
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+
+        self.frames.push_back((tick, frame));
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    /// Drops every frame after `tick` and returns the newest one left,
+    /// or `None` without changing anything if `tick` is older than the whole buffer
+    pub fn rewind_to(&mut self, tick: u64) -> Option<(u64, T)> {
+        if self.frames.front().is_none_or(|(oldest, _)| *oldest > tick) {
+            return None;
+        }
+
+        while self.frames.back().is_some_and(|(last, _)| *last > tick) {
+            self.frames.pop_back();
+        }
+
+        self.frames.back().cloned()
+    }
+}
@@ -0,0 +1,613 @@
+use crate::utils::game::{GAME_DT, GAME_TPS};
+use rlbot_sockets::flat;
+use rocketsim_rs::{
+    math::Vec3,
+    sim::{CarControls, CarState},
+    GameState,
+};
+
+const TPS: u32 = GAME_TPS as u32;
+
+/// How long a player waits for a new item after using their last one
+const ITEM_INTERVAL_TICKS: u32 = 10 * TPS;
+/// The first items of a kickoff are handed out this long after the kickoff starts
+const KICKOFF_ITEM_DELAY_TICKS: u32 = 3 * TPS;
+
+const ORANGE_GOAL_Y: f32 = 5120.;
+const BALL_FROZEN_TICKS: u32 = 2 * TPS;
+const SPIKES_TICKS: u32 = 12 * TPS;
+const PLUNGER_TICKS: u32 = 2 * TPS;
+const GRAPPLE_TICKS: u32 = 3 * TPS / 2;
+const DISRUPTOR_TICKS: u32 = 3 * TPS;
+const TORNADO_TICKS: u32 = 4 * TPS;
+const POWER_HITTER_TICKS: u32 = 12 * TPS;
+
+/// Items that need a target are wasted if there's nothing within this range
+const ITEM_RANGE: f32 = 2500.;
+const TORNADO_RADIUS: f32 = 600.;
+const POWER_HITTER_DEMO_RANGE: f32 = 200.;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Item {
+    Spikes,
+    Plunger,
+    Boot,
+    Haymaker,
+    GrapplingHook,
+    Freezer,
+    Disruptor,
+    Tornado,
+    PowerHitter,
+}
+
+impl Item {
+    const ALL: [Self; 9] = [
+        Self::Spikes,
+        Self::Plunger,
+        Self::Boot,
+        Self::Haymaker,
+        Self::GrapplingHook,
+        Self::Freezer,
+        Self::Disruptor,
+        Self::Tornado,
+        Self::PowerHitter,
+    ];
+
+    pub const fn to_flat(self) -> flat::RumbleItem {
+        match self {
+            Self::Spikes => flat::RumbleItem::BallVelcro,
+            Self::Plunger => flat::RumbleItem::BallLasso,
+            Self::Boot => flat::RumbleItem::CarSpring,
+            Self::Haymaker => flat::RumbleItem::BallSpring,
+            Self::GrapplingHook => flat::RumbleItem::BallGrapplingHook,
+            Self::Freezer => flat::RumbleItem::BallFreeze,
+            Self::Disruptor => flat::RumbleItem::BoostOverride,
+            Self::Tornado => flat::RumbleItem::Tornado,
+            Self::PowerHitter => flat::RumbleItem::StrongHit,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct ActiveItem {
+    item: Item,
+    ticks_remaining: u32,
+    /// The car id an item like the disruptor was used on
+    target: Option<u32>,
+    /// Where the tornado was placed
+    position: Vec3,
+    /// The tick the item was used on, so only touches after it count
+    used_tick: u64,
+}
+
+#[derive(Clone)]
+struct RumblePlayer {
+    car_id: u32,
+    team: u32,
+    held_item: Option<Item>,
+    ticks_until_item: u32,
+    wants_to_use_item: bool,
+    active: Option<ActiveItem>,
+}
+
+/// What a tick of rumble did that has to be applied outside of the game state
+#[derive(Default)]
+pub struct RumbleEffects {
+    /// Whether the game state was changed, so it has to be set on the arena
+    pub state_changed: bool,
+    /// Controls that have to be forced onto cars
+    pub overrides: Vec<(u32, CarControls)>,
+    /// `(attacker, victim)` car ids of everyone demolished by the power hitter
+    pub demolitions: Vec<(u32, u32)>,
+}
+
+/// Emulates rumble by handing out items on a timer,
+/// then applying them to the arena as forces, state overrides and control overrides
+#[derive(Clone)]
+pub struct Rumble {
+    players: Vec<RumblePlayer>,
+    rng: u32,
+    /// Set while the spikes of the car with this id are holding the ball
+    ball_stuck_to: Option<u32>,
+    ball_frozen_ticks: u32,
+}
+
+impl Rumble {
+    /// Starts a new match with the given `(car_id, team)` for each player index
+    pub fn new(players: impl Iterator<Item = (u32, u32)>, seed: u32) -> Self {
+        let mut rumble = Self {
+            players: players
+                .map(|(car_id, team)| RumblePlayer {
+                    car_id,
+                    team,
+                    held_item: None,
+                    ticks_until_item: 0,
+                    wants_to_use_item: false,
+                    active: None,
+                })
+                .collect(),
+            rng: seed,
+            ball_stuck_to: None,
+            ball_frozen_ticks: 0,
+        };
+
+        rumble.start_kickoff();
+        rumble
+    }
+
+    /// Items don't carry over between kickoffs
+    pub fn start_kickoff(&mut self) {
+        for player in &mut self.players {
+            player.held_item = None;
+            player.ticks_until_item = KICKOFF_ITEM_DELAY_TICKS;
+            player.wants_to_use_item = false;
+            player.active = None;
+        }
+
+        self.ball_stuck_to = None;
+        self.ball_frozen_ticks = 0;
+    }
+
+    /// Moves the ticks items were used on forward, for when the arena's ticks were rewound
+    pub fn shift_ticks(&mut self, ticks: u64) {
+        for active in self
+            .players
+            .iter_mut()
+            .filter_map(|player| player.active.as_mut())
+        {
+            active.used_tick += ticks;
+        }
+    }
+
+    #[inline]
+    pub fn held_item(&self, player_index: usize) -> Option<Item> {
+        self.players.get(player_index)?.held_item
+    }
+
+    /// The item gets used on the next tick
+    pub fn use_item(&mut self, player_index: usize) {
+        if let Some(player) = self.players.get_mut(player_index) {
+            player.wants_to_use_item = player.held_item.is_some();
+        }
+    }
+
+    fn random_item(&mut self) -> Item {
+        self.rng = self.rng.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        Item::ALL[(self.rng >> 16) as usize % Item::ALL.len()]
+    }
+
+    /// Hands out items, then applies every active item to `game_state`.
+    /// Cars demolished by an item respawn after `respawn_delay` seconds.
+    /// The returned effects say whether `game_state` was changed at all.
+    pub fn tick(&mut self, game_state: &mut GameState, respawn_delay: f32) -> RumbleEffects {
+        for i in 0..self.players.len() {
+            if self.players[i].held_item.is_some() || self.players[i].active.is_some() {
+                continue;
+            }
+
+            if self.players[i].ticks_until_item == 0 {
+                self.players[i].held_item = Some(self.random_item());
+            } else {
+                self.players[i].ticks_until_item -= 1;
+            }
+        }
+
+        let mut effects = RumbleEffects::default();
+
+        for i in 0..self.players.len() {
+            if self.players[i].wants_to_use_item {
+                self.activate(i, game_state, &mut effects);
+            }
+        }
+
+        for i in 0..self.players.len() {
+            let Some(mut active) = self.players[i].active else {
+                continue;
+            };
+
+            self.apply(i, &active, game_state, respawn_delay, &mut effects);
+
+            active.ticks_remaining = active.ticks_remaining.saturating_sub(1);
+            if active.ticks_remaining == 0 {
+                self.finish(i);
+            } else {
+                self.players[i].active = Some(active);
+            }
+        }
+
+        if let Some(car_id) = self.ball_stuck_to {
+            match game_state.cars.iter().find(|car| car.id == car_id) {
+                Some(car) if !car.state.is_demoed => {
+                    game_state.ball.pos = add(car.state.pos, scale(car.state.rot_mat.up, 120.));
+                    game_state.ball.vel = car.state.vel;
+                    game_state.ball.ang_vel = Vec3::new(0., 0., 0.);
+                    effects.state_changed = true;
+                }
+                // a demolition knocks the ball loose
+                _ => self.ball_stuck_to = None,
+            }
+        }
+
+        if self.ball_frozen_ticks > 0 {
+            self.ball_frozen_ticks -= 1;
+            game_state.ball.vel = Vec3::new(0., 0., 0.);
+            game_state.ball.ang_vel = Vec3::new(0., 0., 0.);
+            effects.state_changed = true;
+        }
+
+        effects
+    }
+
+    fn finish(&mut self, player_index: usize) {
+        let player = &mut self.players[player_index];
+
+        if self.ball_stuck_to == Some(player.car_id) {
+            self.ball_stuck_to = None;
+        }
+
+        player.active = None;
+        player.ticks_until_item = ITEM_INTERVAL_TICKS;
+    }
+
+    /// The closest opponent that isn't demolished and is within `ITEM_RANGE`
+    fn nearest_opponent(&self, player_index: usize, game_state: &GameState) -> Option<u32> {
+        let player = &self.players[player_index];
+        let pos = car_state(game_state, player.car_id)?.pos;
+
+        self.players
+            .iter()
+            .filter(|other| other.team != player.team)
+            .filter_map(|other| Some((other.car_id, car_state(game_state, other.car_id)?)))
+            .filter(|(_, state)| !state.is_demoed)
+            .map(|(car_id, state)| (car_id, distance(state.pos, pos)))
+            .filter(|(_, dist)| *dist <= ITEM_RANGE)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(car_id, _)| car_id)
+    }
+
+    /// Uses the held item, instant items take effect right away
+    fn activate(
+        &mut self,
+        player_index: usize,
+        game_state: &mut GameState,
+        effects: &mut RumbleEffects,
+    ) {
+        let player = &mut self.players[player_index];
+        player.wants_to_use_item = false;
+
+        let Some(item) = player.held_item.take() else {
+            return;
+        };
+
+        let car_id = player.car_id;
+        let team = player.team;
+        let Some(car) = car_state(game_state, car_id).copied() else {
+            return;
+        };
+
+        let ball = game_state.ball.pos;
+        let ball_in_range = distance(ball, car.pos) <= ITEM_RANGE;
+
+        let mut active = ActiveItem {
+            item,
+            ticks_remaining: 0,
+            target: None,
+            position: ball,
+            used_tick: game_state.tick_count,
+        };
+
+        match item {
+            Item::Spikes => active.ticks_remaining = SPIKES_TICKS,
+            Item::Plunger if ball_in_range => active.ticks_remaining = PLUNGER_TICKS,
+            Item::GrapplingHook if ball_in_range => active.ticks_remaining = GRAPPLE_TICKS,
+            Item::Tornado => active.ticks_remaining = TORNADO_TICKS,
+            Item::PowerHitter => active.ticks_remaining = POWER_HITTER_TICKS,
+            Item::Freezer if ball_in_range => {
+                self.ball_stuck_to = None;
+                self.ball_frozen_ticks = BALL_FROZEN_TICKS;
+            }
+            Item::Haymaker if ball_in_range => {
+                // punch the ball towards the opponent's goal
+                let goal_y = if team == 0 {
+                    ORANGE_GOAL_Y
+                } else {
+                    -ORANGE_GOAL_Y
+                };
+                let direction = normalize(sub(Vec3::new(0., goal_y, ball.z), ball));
+                game_state.ball.vel = add(game_state.ball.vel, scale(direction, 2500.));
+                self.ball_stuck_to = None;
+                effects.state_changed = true;
+            }
+            Item::Boot => {
+                let target = self.nearest_opponent(player_index, game_state);
+                if let Some(target_state) = target.and_then(|id| car_state_mut(game_state, id)) {
+                    let away = normalize(sub(target_state.pos, car.pos));
+                    target_state.vel = add(scale(away, 1500.), Vec3::new(0., 0., 1000.));
+                    target_state.is_on_ground = false;
+                    effects.state_changed = true;
+                }
+            }
+            Item::Disruptor => {
+                active.target = self.nearest_opponent(player_index, game_state);
+                if active.target.is_some() {
+                    active.ticks_remaining = DISRUPTOR_TICKS;
+                }
+            }
+            // out of range, so the item is wasted
+            Item::Plunger | Item::GrapplingHook | Item::Freezer | Item::Haymaker => {}
+        }
+
+        let player = &mut self.players[player_index];
+        if active.ticks_remaining == 0 {
+            player.ticks_until_item = ITEM_INTERVAL_TICKS;
+        } else {
+            player.active = Some(active);
+        }
+    }
+
+    fn apply(
+        &mut self,
+        player_index: usize,
+        active: &ActiveItem,
+        game_state: &mut GameState,
+        respawn_delay: f32,
+        effects: &mut RumbleEffects,
+    ) {
+        let player = &self.players[player_index];
+        let car_id = player.car_id;
+        let team = player.team;
+
+        let Some(car) = car_state(game_state, car_id).copied() else {
+            return;
+        };
+
+        let touched_ball = car.ball_hit_info.is_valid
+            && car.ball_hit_info.tick_count_when_hit >= active.used_tick
+            && car.ball_hit_info.tick_count_when_hit + 1 >= game_state.tick_count;
+
+        match active.item {
+            Item::Spikes => {
+                if touched_ball && self.ball_stuck_to.is_none() {
+                    self.ball_stuck_to = Some(car_id);
+                }
+            }
+            Item::Plunger => {
+                let pull = normalize(sub(car.pos, game_state.ball.pos));
+                game_state.ball.vel = add(game_state.ball.vel, scale(pull, 3000. * GAME_DT));
+                effects.state_changed = true;
+            }
+            Item::GrapplingHook => {
+                let pull = normalize(sub(game_state.ball.pos, car.pos));
+                if let Some(car) = car_state_mut(game_state, car_id) {
+                    car.vel = scale(pull, 2300.);
+                    car.is_on_ground = false;
+                    effects.state_changed = true;
+                }
+            }
+            Item::Disruptor => {
+                // drive the target around at random until it wears off
+                if let Some(target) = active.target {
+                    self.rng = self.rng.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                    let steer = if self.rng & 0x8000 == 0 { -1. } else { 1. };
+
+                    effects.overrides.push((
+                        target,
+                        CarControls {
+                            throttle: 1.,
+                            steer,
+                            pitch: 0.,
+                            yaw: 0.,
+                            roll: 0.,
+                            boost: false,
+                            jump: false,
+                            handbrake: false,
+                        },
+                    ));
+                }
+            }
+            Item::Tornado => {
+                // lift and spin everything that's close to where the tornado was placed
+                let center = active.position;
+                let spin = |pos: Vec3, vel: Vec3| {
+                    let offset = Vec3::new(pos.x - center.x, pos.y - center.y, 0.);
+                    if offset.x.hypot(offset.y) > TORNADO_RADIUS {
+                        return vel;
+                    }
+
+                    let tangent = normalize(Vec3::new(-offset.y, offset.x, 0.));
+                    let lift = Vec3::new(0., 0., 1200. * GAME_DT);
+                    add(add(vel, scale(tangent, 1500. * GAME_DT)), lift)
+                };
+
+                game_state.ball.vel = spin(game_state.ball.pos, game_state.ball.vel);
+                for car in &mut game_state.cars {
+                    car.state.vel = spin(car.state.pos, car.state.vel);
+                }
+                effects.state_changed = true;
+            }
+            Item::PowerHitter => {
+                if touched_ball
+                    && car.ball_hit_info.tick_count_when_hit + 1 == game_state.tick_count
+                {
+                    game_state.ball.vel = scale(game_state.ball.vel, 1.5);
+                    effects.state_changed = true;
+                }
+
+                let opponents = self
+                    .players
+                    .iter()
+                    .filter(|other| other.team != team)
+                    .map(|other| other.car_id);
+
+                for opponent in opponents {
+                    let Some(state) = car_state_mut(game_state, opponent) else {
+                        continue;
+                    };
+
+                    if !state.is_demoed && distance(state.pos, car.pos) <= POWER_HITTER_DEMO_RANGE {
+                        state.is_demoed = true;
+                        state.demo_respawn_timer = respawn_delay;
+                        effects.demolitions.push((car_id, opponent));
+                        effects.state_changed = true;
+                    }
+                }
+            }
+            // instant items never stay active
+            Item::Boot | Item::Haymaker | Item::Freezer => {}
+        }
+    }
+}
+
+fn car_state(game_state: &GameState, car_id: u32) -> Option<&CarState> {
+    game_state
+        .cars
+        .iter()
+        .find(|car| car.id == car_id)
+        .map(|car| &car.state)
+}
+
+fn car_state_mut(game_state: &mut GameState, car_id: u32) -> Option<&mut CarState> {
+    game_state
+        .cars
+        .iter_mut()
+        .find(|car| car.id == car_id)
+        .map(|car| &mut car.state)
+}
+
+#[inline]
+fn add(a: Vec3, b: Vec3) -> Vec3 {
+    Vec3::new(a.x + b.x, a.y + b.y, a.z + b.z)
+}
+
+#[inline]
+fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    Vec3::new(a.x - b.x, a.y - b.y, a.z - b.z)
+}
+
+#[inline]
+fn scale(v: Vec3, factor: f32) -> Vec3 {
+    Vec3::new(v.x * factor, v.y * factor, v.z * factor)
+}
+
+#[inline]
+fn distance(a: Vec3, b: Vec3) -> f32 {
+    (a.x - b.x).hypot(a.y - b.y).hypot(a.z - b.z)
+}
+
+fn normalize(v: Vec3) -> Vec3 {
+    let length = v.x.hypot(v.y).hypot(v.z);
+    if length == 0. {
+        v
+    } else {
+        scale(v, 1. / length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::{car_info, game_state, BLUE_CAR, ORANGE_CAR};
+    use rocketsim_rs::sim::Team;
+
+    fn new_game_state(orange_pos: Vec3) -> GameState {
+        let car = |id, team, pos| {
+            let mut car = car_info(id, team);
+            car.state.pos = pos;
+            car
+        };
+
+        let mut game_state = game_state([
+            car(BLUE_CAR, Team::Blue, Vec3::new(0., 0., 17.)),
+            car(ORANGE_CAR, Team::Orange, orange_pos),
+        ]);
+        game_state.ball.pos = Vec3::new(0., 500., 93.);
+        game_state
+    }
+
+    fn new_rumble(seed: u32) -> Rumble {
+        Rumble::new([(BLUE_CAR, 0), (ORANGE_CAR, 1)].into_iter(), seed)
+    }
+
+    fn use_item(rumble: &mut Rumble, item: Item, game_state: &mut GameState) -> RumbleEffects {
+        rumble.players[0].held_item = Some(item);
+        rumble.use_item(0);
+        rumble.tick(game_state, 3.)
+    }
+
+    #[test]
+    fn items_are_handed_out_after_kickoff() {
+        let mut game_state = new_game_state(Vec3::new(0., 3000., 17.));
+        let mut a = new_rumble(7);
+        let mut b = new_rumble(7);
+
+        for _ in 0..KICKOFF_ITEM_DELAY_TICKS {
+            // nothing happens until items are used
+            assert!(!a.tick(&mut game_state, 3.).state_changed);
+            b.tick(&mut game_state, 3.);
+        }
+        assert_eq!(a.held_item(0), None);
+
+        a.tick(&mut game_state, 3.);
+        b.tick(&mut game_state, 3.);
+        assert!(a.held_item(0).is_some());
+        assert!(a.held_item(1).is_some());
+
+        // the same seed hands out the same items
+        assert_eq!(a.held_item(0), b.held_item(0));
+        assert_eq!(a.held_item(1), b.held_item(1));
+    }
+
+    #[test]
+    fn haymaker_hits_towards_goal() {
+        let mut game_state = new_game_state(Vec3::new(0., 3000., 17.));
+        let mut rumble = new_rumble(0);
+
+        let effects = use_item(&mut rumble, Item::Haymaker, &mut game_state);
+        assert!(effects.state_changed);
+        assert!(game_state.ball.vel.y > 0.);
+        assert_eq!(rumble.held_item(0), None);
+        assert!(rumble.players[0].active.is_none());
+    }
+
+    #[test]
+    fn freezer_stops_ball() {
+        let mut game_state = new_game_state(Vec3::new(0., 3000., 17.));
+        game_state.ball.vel = Vec3::new(100., 200., 300.);
+        let mut rumble = new_rumble(0);
+
+        use_item(&mut rumble, Item::Freezer, &mut game_state);
+        assert_eq!(game_state.ball.vel, Vec3::new(0., 0., 0.));
+    }
+
+    #[test]
+    fn power_hitter_demolishes() {
+        let mut game_state = new_game_state(Vec3::new(100., 0., 17.));
+        let mut rumble = new_rumble(0);
+
+        let effects = use_item(&mut rumble, Item::PowerHitter, &mut game_state);
+        assert_eq!(effects.demolitions, [(BLUE_CAR, ORANGE_CAR)]);
+
+        let victim = car_state(&game_state, ORANGE_CAR).unwrap();
+        assert!(victim.is_demoed);
+        assert_eq!(victim.demo_respawn_timer, 3.);
+
+        // out of range
+        let mut game_state = new_game_state(Vec3::new(1000., 0., 17.));
+        let mut rumble = new_rumble(0);
+        let effects = use_item(&mut rumble, Item::PowerHitter, &mut game_state);
+        assert!(effects.demolitions.is_empty());
+        assert!(!effects.state_changed);
+    }
+
+    #[test]
+    fn wasted_item_changes_nothing() {
+        // the ball is out of the plunger's range
+        let mut game_state = new_game_state(Vec3::new(0., 3000., 17.));
+        game_state.ball.pos = Vec3::new(0., 4000., 93.);
+        let mut rumble = new_rumble(0);
+
+        let effects = use_item(&mut rumble, Item::Plunger, &mut game_state);
+        assert!(!effects.state_changed);
+        assert_eq!(rumble.held_item(0), None);
+    }
+}
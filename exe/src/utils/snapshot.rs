@@ -0,0 +1,27 @@
+use rocketsim_rs::{
+    bytes::{FromBytes, ToBytes},
+    GameState,
+};
+use std::{
+    fs,
+    io::{self, Result as IoResult},
+    path::Path,
+};
+
+/// Writes the full arena state to `path`, in the same format RLViser uses
+pub fn save(path: &Path, game_state: &GameState) -> IoResult<()> {
+    fs::write(path, game_state.to_bytes())
+}
+
+pub fn load(path: &Path) -> IoResult<GameState> {
+    let bytes = fs::read(path)?;
+
+    if bytes.len() < GameState::MIN_NUM_BYTES || GameState::get_num_bytes(&bytes) != bytes.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Not a snapshot file",
+        ));
+    }
+
+    Ok(GameState::from_bytes(&bytes))
+}
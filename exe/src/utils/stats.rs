@@ -0,0 +1,354 @@
+use rlbot_sockets::flat;
+use rocketsim_rs::{consts::SOCCAR_GOAL_SCORE_BASE_THRESHOLD_Y, GameState};
+
+const GOAL_POINTS: u32 = 100;
+const ASSIST_POINTS: u32 = 50;
+const SAVE_POINTS: u32 = 50;
+const SHOT_POINTS: u32 = 20;
+const DEMOLITION_POINTS: u32 = 25;
+
+/// A teammate's touch only counts as an assist if it happened this many ticks before the goal
+const ASSIST_WINDOW_TICKS: u64 = 5 * 120;
+
+#[derive(Clone)]
+struct Touch {
+    player: usize,
+    team: u32,
+    ball: usize,
+    tick: u64,
+}
+
+#[derive(Clone)]
+struct TrackedPlayer {
+    car_id: u32,
+    team: u32,
+    /// The tick of the last hit on each ball, in packet order
+    last_hit_ticks: Vec<Option<u64>>,
+    score: flat::ScoreInfoT,
+}
+
+/// Attributes goals, assists, shots, saves and demolitions to individual players
+#[derive(Clone, Default)]
+pub struct ScoreTracker {
+    players: Vec<TrackedPlayer>,
+    /// Every touch since the last kickoff, oldest first
+    touches: Vec<Touch>,
+    /// The team whose goal the ball was predicted to go into on the previous tick
+    threatened_goal: Option<u32>,
+}
+
+impl ScoreTracker {
+    /// Starts tracking a new match with the given `(car_id, team)` for each player index
+    pub fn reset(&mut self, players: impl Iterator<Item = (u32, u32)>) {
+        self.players = players
+            .map(|(car_id, team)| TrackedPlayer {
+                car_id,
+                team,
+                last_hit_ticks: Vec::new(),
+                score: flat::ScoreInfoT::default(),
+            })
+            .collect();
+
+        self.start_kickoff();
+    }
+
+    pub fn start_kickoff(&mut self) {
+        self.touches.clear();
+        self.threatened_goal = None;
+
+        for player in &mut self.players {
+            player.last_hit_ticks.clear();
+        }
+    }
+
+    /// Moves the recorded touches forward in time, for when the arena's ticks were rewound
+    pub fn shift_ticks(&mut self, ticks: u64) {
+        for touch in &mut self.touches {
+            touch.tick += ticks;
+        }
+
+        for player in &mut self.players {
+            for tick in player.last_hit_ticks.iter_mut().flatten() {
+                *tick += ticks;
+            }
+        }
+    }
+
+    #[inline]
+    pub fn score_info(&self, player_index: usize) -> Option<&flat::ScoreInfoT> {
+        self.players.get(player_index).map(|player| &player.score)
+    }
+
+    /// Records new touches of every ball, then awards shots and saves for the main ball
+    /// by comparing where it's headed now to where it was headed before the touch.
+    /// `extra_states` are the arenas of every ball after the first.
+    pub fn on_tick(
+        &mut self,
+        game_state: &GameState,
+        extra_states: &[GameState],
+        threatened_goal: Option<u32>,
+    ) {
+        let first_new_touch = self.touches.len();
+        self.record_touches(0, game_state);
+        let main_ball_touches = first_new_touch..self.touches.len();
+
+        for (i, extra_state) in extra_states.iter().enumerate() {
+            self.record_touches(i + 1, extra_state);
+        }
+
+        for touch in &self.touches[main_ball_touches] {
+            let player = &mut self.players[touch.player];
+            let opponent = 1 - touch.team;
+
+            if threatened_goal == Some(opponent) && self.threatened_goal != Some(opponent) {
+                player.score.shots += 1;
+                player.score.score += SHOT_POINTS;
+            } else if self.threatened_goal == Some(touch.team)
+                && threatened_goal != Some(touch.team)
+            {
+                player.score.saves += 1;
+                player.score.score += SAVE_POINTS;
+            }
+        }
+
+        self.threatened_goal = threatened_goal;
+    }
+
+    fn record_touches(&mut self, ball: usize, game_state: &GameState) {
+        for (index, player) in self.players.iter_mut().enumerate() {
+            let Some(car) = game_state.cars.iter().find(|car| car.id == player.car_id) else {
+                continue;
+            };
+
+            if player.last_hit_ticks.len() <= ball {
+                player.last_hit_ticks.resize(ball + 1, None);
+            }
+
+            let hit_info = &car.state.ball_hit_info;
+            let last_hit_tick = &mut player.last_hit_ticks[ball];
+            if !hit_info.is_valid || *last_hit_tick == Some(hit_info.tick_count_when_hit) {
+                continue;
+            }
+
+            *last_hit_tick = Some(hit_info.tick_count_when_hit);
+            self.touches.push(Touch {
+                player: index,
+                team: player.team,
+                ball,
+                tick: hit_info.tick_count_when_hit,
+            });
+        }
+    }
+
+    /// Credits the goal to the last player on `scoring_team` to touch the ball that went in,
+    /// and an own goal to the last toucher if they were on the other team
+    pub fn goal_scored(&mut self, scoring_team: u32, ball: usize, tick: u64) {
+        let last_touch = self.touches.iter().rev().find(|touch| touch.ball == ball);
+
+        if let Some(last_touch) = last_touch {
+            if last_touch.team != scoring_team {
+                self.players[last_touch.player].score.own_goals += 1;
+            }
+        }
+
+        let mut team_touches = self
+            .touches
+            .iter()
+            .rev()
+            .filter(|touch| touch.ball == ball && touch.team == scoring_team);
+
+        let Some(scorer) = team_touches.next().map(|touch| touch.player) else {
+            return;
+        };
+
+        let score = &mut self.players[scorer].score;
+        score.goals += 1;
+        score.score += GOAL_POINTS;
+
+        let assister = team_touches
+            .take_while(|touch| touch.tick + ASSIST_WINDOW_TICKS >= tick)
+            .find(|touch| touch.player != scorer)
+            .map(|touch| touch.player);
+
+        if let Some(assister) = assister {
+            let score = &mut self.players[assister].score;
+            score.assists += 1;
+            score.score += ASSIST_POINTS;
+        }
+    }
+
+    pub fn demolition(&mut self, attacker_car_id: u32) {
+        if let Some(player) = self
+            .players
+            .iter_mut()
+            .find(|player| player.car_id == attacker_car_id)
+        {
+            player.score.demolitions += 1;
+            player.score.score += DEMOLITION_POINTS;
+        }
+    }
+}
+
+/// Returns the team whose goal the predicted ball path ends up in, if any
+pub fn threatened_goal(
+    game_mode: flat::GameMode,
+    prediction: &flat::BallPredictionT,
+    ball_radius: f32,
+) -> Option<u32> {
+    match game_mode {
+        flat::GameMode::Soccer
+        | flat::GameMode::Heatseeker
+        | flat::GameMode::Hockey
+        | flat::GameMode::Rumble => {}
+        // hoops and dropshot goals aren't behind the back wall
+        _ => return None,
+    }
+
+    prediction
+        .slices
+        .iter()
+        .map(|slice| slice.physics.location.y)
+        .find(|y| y.abs() > SOCCAR_GOAL_SCORE_BASE_THRESHOLD_Y + ball_radius)
+        .map(|y| u32::from(y > 0.))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::{car_info, game_state, BLUE_CAR, ORANGE_CAR};
+    use rocketsim_rs::sim::Team;
+
+    const SECOND_BLUE_CAR: u32 = 3;
+
+    /// A game state where `hitter` last touched the ball on `tick`
+    fn touched_by(hitter: u32, tick: u64) -> GameState {
+        let car = |id, team| {
+            let mut car = car_info(id, team);
+            if id == hitter {
+                car.state.ball_hit_info.is_valid = true;
+                car.state.ball_hit_info.tick_count_when_hit = tick;
+            }
+            car
+        };
+
+        game_state([
+            car(BLUE_CAR, Team::Blue),
+            car(ORANGE_CAR, Team::Orange),
+            car(SECOND_BLUE_CAR, Team::Blue),
+        ])
+    }
+
+    fn two_on_one() -> ScoreTracker {
+        let mut tracker = ScoreTracker::default();
+        tracker.reset([(BLUE_CAR, 0), (ORANGE_CAR, 1), (SECOND_BLUE_CAR, 0)].into_iter());
+        tracker
+    }
+
+    #[test]
+    fn shot_then_save() {
+        let mut tracker = two_on_one();
+
+        // orange sends the ball towards blue's goal, then blue clears it
+        tracker.on_tick(&touched_by(ORANGE_CAR, 10), &[], Some(0));
+        tracker.on_tick(&touched_by(BLUE_CAR, 20), &[], None);
+
+        let orange = tracker.score_info(1).unwrap();
+        assert_eq!((orange.shots, orange.score), (1, SHOT_POINTS));
+
+        let blue = tracker.score_info(0).unwrap();
+        assert_eq!((blue.saves, blue.score), (1, SAVE_POINTS));
+    }
+
+    #[test]
+    fn touch_keeping_the_ball_on_target_is_not_another_shot() {
+        let mut tracker = two_on_one();
+
+        tracker.on_tick(&touched_by(BLUE_CAR, 10), &[], Some(1));
+        tracker.on_tick(&touched_by(SECOND_BLUE_CAR, 20), &[], Some(1));
+
+        assert_eq!(tracker.score_info(0).unwrap().shots, 1);
+        assert_eq!(tracker.score_info(2).unwrap().shots, 0);
+    }
+
+    #[test]
+    fn assist_within_window() {
+        let mut tracker = two_on_one();
+
+        tracker.on_tick(&touched_by(SECOND_BLUE_CAR, 100), &[], None);
+        tracker.on_tick(&touched_by(BLUE_CAR, 200), &[], None);
+        tracker.goal_scored(0, 0, 100 + ASSIST_WINDOW_TICKS);
+
+        let scorer = tracker.score_info(0).unwrap();
+        assert_eq!((scorer.goals, scorer.assists), (1, 0));
+
+        let assister = tracker.score_info(2).unwrap();
+        assert_eq!((assister.assists, assister.score), (1, ASSIST_POINTS));
+    }
+
+    #[test]
+    fn no_assist_after_window() {
+        let mut tracker = two_on_one();
+
+        tracker.on_tick(&touched_by(SECOND_BLUE_CAR, 100), &[], None);
+        tracker.on_tick(&touched_by(BLUE_CAR, 200), &[], None);
+        tracker.goal_scored(0, 0, 101 + ASSIST_WINDOW_TICKS);
+
+        assert_eq!(tracker.score_info(0).unwrap().goals, 1);
+        assert_eq!(tracker.score_info(2).unwrap().assists, 0);
+    }
+
+    #[test]
+    fn own_goal() {
+        let mut tracker = two_on_one();
+
+        // orange touches the ball last and it goes into orange's goal
+        tracker.on_tick(&touched_by(BLUE_CAR, 10), &[], None);
+        tracker.on_tick(&touched_by(ORANGE_CAR, 20), &[], None);
+        tracker.goal_scored(0, 0, 30);
+
+        let orange = tracker.score_info(1).unwrap();
+        assert_eq!((orange.own_goals, orange.goals), (1, 0));
+
+        let blue = tracker.score_info(0).unwrap();
+        assert_eq!((blue.goals, blue.score), (1, GOAL_POINTS));
+    }
+
+    #[test]
+    fn demolition_points() {
+        let mut tracker = two_on_one();
+
+        tracker.demolition(ORANGE_CAR);
+        // cars that aren't players are ignored
+        tracker.demolition(99);
+
+        let orange = tracker.score_info(1).unwrap();
+        assert_eq!((orange.demolitions, orange.score), (1, DEMOLITION_POINTS));
+        assert_eq!(tracker.score_info(0).unwrap().score, 0);
+    }
+
+    #[test]
+    fn goal_by_extra_ball() {
+        let mut tracker = ScoreTracker::default();
+        tracker.reset([(BLUE_CAR, 0), (ORANGE_CAR, 1)].into_iter());
+
+        // blue has the main ball, orange hits the second one afterwards
+        tracker.on_tick(
+            &touched_by(BLUE_CAR, 10),
+            &[touched_by(ORANGE_CAR, 5)],
+            None,
+        );
+        tracker.on_tick(
+            &touched_by(BLUE_CAR, 10),
+            &[touched_by(ORANGE_CAR, 20)],
+            None,
+        );
+
+        tracker.goal_scored(1, 1, 30);
+
+        let blue = tracker.score_info(0).unwrap();
+        assert_eq!((blue.goals, blue.own_goals), (0, 0));
+
+        let orange = tracker.score_info(1).unwrap();
+        assert_eq!(orange.goals, 1);
+    }
+}
@@ -0,0 +1,110 @@
+use rlbot_sockets::flat;
+use std::{
+    fs::File,
+    io::{BufWriter, Result as IoResult, Write},
+    path::Path,
+};
+
+const HEADER: &str = "frame,seconds_elapsed,match_phase,blue_score,orange_score,\
+player_index,team,x,y,z,vel_x,vel_y,vel_z,pitch,yaw,roll,boost,air_state,is_demolished,\
+throttle,steer,input_pitch,input_yaw,input_roll,jump,input_boost,handbrake,\
+ball_x,ball_y,ball_z,ball_vel_x,ball_vel_y,ball_vel_z";
+
+/// Writes one CSV row per car for every game packet of a new tick
+pub struct TelemetryWriter {
+    writer: BufWriter<File>,
+    /// The frame of the last packet that was written
+    last_frame: Option<u32>,
+}
+
+impl TelemetryWriter {
+    pub fn create(path: &Path) -> IoResult<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "{HEADER}")?;
+
+        Ok(Self {
+            writer,
+            last_frame: None,
+        })
+    }
+
+    /// Skips packets that repeat the last frame, like the ones sent while paused,
+    /// and flushes every row so the file is complete even if the server is killed
+    pub fn write_packet(&mut self, packet: &flat::GamePacketT) -> IoResult<()> {
+        let info = &packet.match_info;
+        // a rewind turns the frame back, the replayed ticks still get their rows
+        if self.last_frame == Some(info.frame_num) {
+            return Ok(());
+        }
+        self.last_frame = Some(info.frame_num);
+
+        let blue_score = packet.teams.first().map_or(0, |team| team.score);
+        let orange_score = packet.teams.get(1).map_or(0, |team| team.score);
+        let ball = packet
+            .balls
+            .first()
+            .map(|ball| &ball.physics)
+            .cloned()
+            .unwrap_or_default();
+
+        for (i, player) in packet.players.iter().enumerate() {
+            let physics = &player.physics;
+            let input = &player.last_input;
+
+            let w = &mut self.writer;
+
+            write!(
+                w,
+                "{},{},{},{blue_score},{orange_score},{i},{},",
+                info.frame_num,
+                info.seconds_elapsed,
+                info.match_phase.variant_name().unwrap_or_default(),
+                player.team,
+            )?;
+            write!(
+                w,
+                "{},{},{},{},{},{},",
+                physics.location.x,
+                physics.location.y,
+                physics.location.z,
+                physics.velocity.x,
+                physics.velocity.y,
+                physics.velocity.z,
+            )?;
+            write!(
+                w,
+                "{},{},{},{},{},{},",
+                physics.rotation.pitch,
+                physics.rotation.yaw,
+                physics.rotation.roll,
+                player.boost,
+                player.air_state.variant_name().unwrap_or_default(),
+                player.demolished_timeout >= 0.,
+            )?;
+            write!(
+                w,
+                "{},{},{},{},{},{},{},{},",
+                input.throttle,
+                input.steer,
+                input.pitch,
+                input.yaw,
+                input.roll,
+                input.jump,
+                input.boost,
+                input.handbrake,
+            )?;
+            writeln!(
+                w,
+                "{},{},{},{},{},{}",
+                ball.location.x,
+                ball.location.y,
+                ball.location.z,
+                ball.velocity.x,
+                ball.velocity.y,
+                ball.velocity.z,
+            )?;
+        }
+
+        self.writer.flush()
+    }
+}
@@ -0,0 +1,69 @@
+//! Helpers shared by the unit tests
+use rocketsim_rs::{
+    cxx::UniquePtr,
+    math::Vec3,
+    sim::{Arena, CarConfig, Team},
+    CarInfo, GameState,
+};
+
+pub const BLUE_CAR: u32 = 1;
+pub const ORANGE_CAR: u32 = 2;
+
+/// RocketSim won't make a soccar arena without a collision mesh,
+/// but the floor, ceiling and side walls are always added as planes,
+/// so tests load a single triangle up by the ceiling instead of the real meshes
+pub fn init_test_meshes() {
+    static INIT: std::sync::Once = std::sync::Once::new();
+
+    INIT.call_once(|| {
+        let mut mesh = Vec::new();
+        for int in [1i32, 3, 0, 1, 2] {
+            mesh.extend_from_slice(&int.to_le_bytes());
+        }
+        for float in [0f32, 0., 2000., 10., 0., 2000., 0., 10., 2000.] {
+            mesh.extend_from_slice(&float.to_le_bytes());
+        }
+
+        rocketsim_rs::init_from_mem(&[&mesh], &[]);
+    });
+}
+
+/// A car for a hand-built [`GameState`], with every other field left at its default
+pub fn car_info(id: u32, team: Team) -> CarInfo {
+    let mut car = CarInfo::default();
+    car.id = id;
+    car.team = team;
+    car
+}
+
+pub fn game_state(cars: impl IntoIterator<Item = CarInfo>) -> GameState {
+    let mut game_state = GameState::default();
+    game_state.cars = cars.into_iter().collect();
+    game_state
+}
+
+pub fn add_car(arena: &mut UniquePtr<Arena>, team: Team, pos: Vec3, vel: Vec3) -> u32 {
+    let car_id = arena.pin_mut().add_car(team, CarConfig::octane());
+
+    let mut state = arena.pin_mut().get_car(car_id);
+    state.pos = pos;
+    state.vel = vel;
+    arena.pin_mut().set_car(car_id, state).unwrap();
+
+    car_id
+}
+
+/// A car that has settled onto its wheels away from the ball
+pub fn arena_with_car() -> (UniquePtr<Arena>, u32) {
+    init_test_meshes();
+    let mut arena = Arena::default_standard();
+    let car_id = add_car(
+        &mut arena,
+        Team::Blue,
+        Vec3::new(0., -2000., 17.),
+        Vec3::new(0., 0., 0.),
+    );
+    arena.pin_mut().step(30);
+
+    (arena, car_id)
+}